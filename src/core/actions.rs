@@ -5,45 +5,53 @@ use super::command::*;
 use super::{state::*, entities::*};
 
 #[enum_dispatch(Command)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Action {
     PlaceRing,
     PlaceMarker,
     MoveRing,
     RemoveRun,
     RemoveRing,
+    DeferMove,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PlaceRing {
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PlaceMarker {
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MoveRing {
     pub from: HexCoord,
     pub to: HexCoord,
     pub player: Player,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RemoveRun {
     pub run_idx: usize,
     pub run: Vec<HexCoord>,
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RemoveRing {
     pub coord: HexCoord,
     pub player: Player,
 }
 
+// variant only: takes the marker back into the ring and passes the turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeferMove {
+    pub coord: HexCoord,
+    pub player: Player,
+}
+
 impl Command for PlaceRing {
     fn is_legal(&self, state: &State) -> bool {
         state.at_phase(&Phase::PlaceRing) && state.board.free_board_field(&self.coord)
@@ -53,19 +61,20 @@ impl Command for PlaceRing {
         state.new_action();
         state.place_ring(&state.current_player.clone(), &self.coord);
 
-        if state.board.rings().count() > 9 {
+        if state.board.n_rings() >= 2 * state.options.rings_per_player {
             state.set_phase(Phase::PlaceMarker);
         }
 
         state.next_player();
-        state.history.push(Action::from(self.clone()));
+        state.end_if_blocked();
+        state.push_history(Action::from(self.clone()));
     }
 
     fn undo(&self, state: &mut State) {
         state.new_action();
+        state.next_player();
         state.remove_ring(&state.current_player.clone(), &self.coord);
         state.set_phase(Phase::PlaceRing);
-        state.next_player();
     }
 
     fn coord(&self) -> HexCoord {
@@ -77,18 +86,21 @@ impl Command for PlaceMarker {
     fn is_legal(&self, state: &State) -> bool {
         state.at_phase(&Phase::PlaceMarker)
             && state.board.player_ring_at(&self.coord, &state.current_player)
+            && state.can_place_marker(&self.coord)
     }
 
     fn execute(&self, state: &mut State) {
         state.new_action();
         state.place_marker(&state.current_player.clone(), &self.coord);
+        state.compute_runs();
         state.set_phase(Phase::MoveRing(self.coord));
-        state.history.push(Action::from(self.clone()));
+        state.push_history(Action::from(self.clone()));
     }
 
     fn undo(&self, state: &mut State) {
         state.new_action();
         state.place_ring(&state.current_player.clone(), &self.coord);
+        state.compute_runs();
         state.set_phase(Phase::PlaceMarker);
     }
 
@@ -102,12 +114,7 @@ impl Command for MoveRing {
         if !state.at_phase(&Phase::MoveRing(self.from)) {
             return false;
         }
-        return state
-            .board
-            .ring_targets(&self.from)
-            .iter()
-            .find(|&c| c == &self.to)
-            .is_some();
+        state.board.ring_can_reach(&self.from, &self.to)
     }
 
     fn execute(&self, state: &mut State) {
@@ -125,9 +132,10 @@ impl Command for MoveRing {
         } else {
             state.set_phase(Phase::PlaceMarker);
             state.next_player();
+            state.end_if_blocked();
         }
 
-        state.history.push(Action::from(self.clone()));
+        state.push_history(Action::from(self.clone()));
     }
 
     fn undo(&self, state: &mut State) {
@@ -159,7 +167,7 @@ impl Command for RemoveRun {
 
         state.compute_runs();
         state.set_phase(Phase::RemoveRing);
-        state.history.push(Action::from(self.clone()));
+        state.push_history(Action::from(self.clone()));
     }
 
     fn undo(&self, state: &mut State) {
@@ -185,14 +193,14 @@ impl Command for RemoveRing {
 
     fn execute(&self, state: &mut State) {
         state.new_action();
-        state.history.push(Action::from(self.clone()));
+        state.push_history(Action::from(self.clone()));
 
         state.remove_ring(&state.current_player.clone(), &self.coord);
 
         let current_player = state.current_player;
         state.inc_score(&current_player);
 
-        if state.get_score(&current_player) == 3 {
+        if state.get_score(&current_player) == state.win_score() {
             state.set_phase(Phase::PlayerWon(current_player));
             return;
         }
@@ -202,12 +210,21 @@ impl Command for RemoveRing {
             return;
         }
 
+        // runs made for the opponent are resolved at the start of their turn, which they then
+        // continue, only the player who moved passes the turn on
+        if state.last_mover().is_some_and(|mover| mover != current_player) {
+            state.set_phase(Phase::PlaceMarker);
+            state.end_if_blocked();
+            return;
+        }
+
         state.next_player();
 
         if state.has_run(&state.current_player) {
             state.set_phase(Phase::RemoveRun);
         } else {
             state.set_phase(Phase::PlaceMarker);
+            state.end_if_blocked();
         }
     }
 
@@ -218,6 +235,37 @@ impl Command for RemoveRing {
         state.set_phase(Phase::RemoveRing);
 
         state.place_ring(&state.current_player.clone(), &self.coord);
+        state.compute_runs();
+    }
+
+    fn coord(&self) -> HexCoord {
+        self.coord
+    }
+}
+
+impl Command for DeferMove {
+    fn is_legal(&self, state: &State) -> bool {
+        state.options.allow_marker_deferral
+            && state.at_phase(&Phase::MoveRing(self.coord))
+            && state.current_player == self.player
+    }
+
+    fn execute(&self, state: &mut State) {
+        state.new_action();
+        state.place_ring(&state.current_player.clone(), &self.coord);
+        state.compute_runs();
+        state.set_phase(Phase::PlaceMarker);
+        state.next_player();
+        state.end_if_blocked();
+        state.push_history(Action::from(self.clone()));
+    }
+
+    fn undo(&self, state: &mut State) {
+        state.new_action();
+        state.current_player = self.player;
+        state.place_marker(&state.current_player.clone(), &self.coord);
+        state.compute_runs();
+        state.set_phase(Phase::MoveRing(self.coord));
     }
 
     fn coord(&self) -> HexCoord {
@@ -228,13 +276,14 @@ impl Command for RemoveRing {
 #[cfg(test)]
 mod test {
     use crate::core::board::Board;
+    use crate::core::options::GameOptions;
 
     use super::*;
 
     #[test]
     fn test_place_ring() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
+        state.set_turn(Player::White, Phase::PlaceRing);
 
         let c = HexCoord::new(2, 4);
         let action = PlaceRing { coord: c };
@@ -253,7 +302,7 @@ mod test {
     #[test]
     fn test_place_ring_on_occupied_not_allowed() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
+        state.set_turn(Player::White, Phase::PlaceRing);
 
         let c = HexCoord::new(2, 4);
         state.board
@@ -267,8 +316,7 @@ mod test {
     #[test]
     fn test_place_ring_in_wrong_phase_not_allowed() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 4);
         let action = PlaceRing { coord: c };
@@ -279,7 +327,7 @@ mod test {
     #[test]
     fn test_place_ring_undo() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
+        state.set_turn(Player::White, Phase::PlaceRing);
 
         let occupied = HexCoord::new(-1, 1);
         state.board
@@ -308,8 +356,7 @@ mod test {
     #[test]
     fn test_place_marker() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 4);
 
@@ -323,14 +370,63 @@ mod test {
         assert_eq!(state.board.rings().count(), 0);
         assert!(state.board.player_marker_at(&c, &Player::White));
         assert_eq!(state.current_player, Player::White);
-        matches!(state.current_phase, Phase::MoveRing(_));
+        assert!(matches!(state.current_phase, Phase::MoveRing(_)));
+
+        // recorded as a marker placement, not a ring one
+        let changes = state.last_state_change();
+        assert!(changes.contains(&StateChange::MarkerPlaced(Player::White, c)));
+        assert!(!changes
+            .iter()
+            .any(|change| matches!(change, StateChange::RingPlaced(..))));
+    }
+
+    #[test]
+    fn test_defer_move_only_with_option() {
+        let c = HexCoord::new(2, 4);
+        let place_marker = PlaceMarker { coord: c };
+        let defer = DeferMove {
+            coord: c,
+            player: Player::White,
+        };
+
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::White, Phase::PlaceMarker);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &c);
+        place_marker.execute(&mut state);
+        assert_eq!(state.current_phase, Phase::MoveRing(c));
+        assert!(!defer.is_legal(&state));
+        assert!(state
+            .legal_moves()
+            .iter()
+            .all(|m| matches!(m, Action::MoveRing(_))));
+
+        let options = GameOptions {
+            allow_marker_deferral: true,
+            ..Default::default()
+        };
+        let mut state = State::with_options(Board::new(), options);
+        state.set_turn(Player::White, Phase::PlaceMarker);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &c);
+        place_marker.execute(&mut state);
+        assert_eq!(state.current_phase, Phase::MoveRing(c));
+        assert!(defer.is_legal(&state));
+
+        state.apply(&Action::from(defer.clone())).unwrap();
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.current_player, Player::Black);
+        assert!(state.board.player_ring_at(&c, &Player::White));
+        assert_eq!(state.board.markers().count(), 0);
+
+        state.undo();
+        assert_eq!(state.current_phase, Phase::MoveRing(c));
+        assert_eq!(state.current_player, Player::White);
+        assert!(state.board.player_marker_at(&c, &Player::White));
     }
 
     #[test]
     fn test_place_marker_without_ring() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 4);
         let action = PlaceMarker { coord: c };
@@ -341,8 +437,7 @@ mod test {
     #[test]
     fn test_place_marker_wrong_player_ring() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 4);
         state.board.place_unchecked(&Piece::Ring(Player::Black), &c);
@@ -357,8 +452,7 @@ mod test {
     #[test]
     fn test_place_marker_in_wrong_phase_not_allowed() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::MoveRing(HexCoord::new(0, 0)));
+        state.set_turn(Player::White, Phase::MoveRing(HexCoord::new(0, 0)));
 
         let c = HexCoord::new(2, 4);
         let action = PlaceMarker { coord: c };
@@ -369,8 +463,7 @@ mod test {
     #[test]
     fn test_place_marker_undo() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 4);
         state.board.place_unchecked(&Piece::Ring(Player::White), &c);
@@ -389,10 +482,8 @@ mod test {
     #[test]
     fn test_move_ring_without_run() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-1, -2);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
 
         let to_coord = HexCoord::new(-1, 4);
         let action = MoveRing {
@@ -413,10 +504,8 @@ mod test {
     #[test]
     fn test_move_ring_in_wrong_phase() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-1, -2);
-
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         // not connected
         let to_coord = HexCoord::new(0, 4);
@@ -431,10 +520,8 @@ mod test {
     #[test]
     fn test_move_ring_to_illegal_field_not_allowed() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-1, -2);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
 
         // not connected
         let to_coord = HexCoord::new(0, 4);
@@ -473,10 +560,8 @@ mod test {
     #[test]
     fn test_move_ring_flips_markers() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-2, 0);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
         state.board
             .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 0));
         state.board
@@ -504,10 +589,8 @@ mod test {
     #[test]
     fn test_move_ring_creates_run_from_placement() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-2, 0);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
         for i in -2..=2 {
             state.board
                 .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
@@ -528,15 +611,16 @@ mod test {
         assert_eq!(state.current_player, Player::White);
         assert_eq!(state.current_phase, Phase::RemoveRun);
         assert!(state.board.player_ring_at(&to_coord, &Player::White));
+        assert!(state
+            .last_state_change()
+            .contains(&StateChange::PhaseChanged(Phase::RemoveRun)));
     }
 
     #[test]
     fn test_move_ring_creates_run_from_flip() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-2, -1);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
         state.board
             .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 0));
         for i in -1..=2 {
@@ -564,10 +648,8 @@ mod test {
     #[test]
     fn test_move_ring_undo() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
         let from_coord = HexCoord::new(-2, -1);
-
-        state.set_phase(Phase::MoveRing(from_coord));
+        state.set_turn(Player::White, Phase::MoveRing(from_coord));
         state.board
             .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 0));
         for i in -1..=2 {
@@ -600,8 +682,7 @@ mod test {
     #[test]
     fn test_remove_run() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRun);
+        state.set_turn(Player::White, Phase::RemoveRun);
 
         let mut run = vec![];
 
@@ -639,8 +720,7 @@ mod test {
     #[test]
     fn test_remove_run_wrong_phase() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let mut run = vec![];
 
@@ -667,8 +747,7 @@ mod test {
     #[test]
     fn test_remove_run_illegal_index() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let mut run = vec![];
 
@@ -694,8 +773,7 @@ mod test {
     #[test]
     fn test_remove_run_undo() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRun);
+        state.set_turn(Player::White, Phase::RemoveRun);
 
         let mut run = vec![];
 
@@ -733,8 +811,7 @@ mod test {
     fn test_remove_ring() {
         for player in [Player::White, Player::Black] {
         let mut state = State::new(Board::new());
-            state.current_player = player;
-            state.set_phase(Phase::RemoveRing);
+            state.set_turn(player, Phase::RemoveRing);
 
             let c = HexCoord::new(2, 3);
             state.board.place_unchecked(&Piece::Ring(player), &c);
@@ -765,8 +842,7 @@ mod test {
     #[test]
     fn test_remove_ring_wrong_phase() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::PlaceMarker);
+        state.set_turn(Player::White, Phase::PlaceMarker);
 
         let c = HexCoord::new(2, 3);
         state.board.place_unchecked(&Piece::Ring(Player::White), &c);
@@ -782,8 +858,7 @@ mod test {
     #[test]
     fn test_remove_ring_wrong_pos() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRing);
+        state.set_turn(Player::White, Phase::RemoveRing);
 
         let c = HexCoord::new(2, 3);
         state.board.place_unchecked(&Piece::Ring(Player::White), &c);
@@ -799,8 +874,7 @@ mod test {
     #[test]
     fn test_remove_ring_wrong_player() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRing);
+        state.set_turn(Player::White, Phase::RemoveRing);
 
         let c = HexCoord::new(2, 3);
         state.board.place_unchecked(&Piece::Ring(Player::White), &c);
@@ -816,8 +890,7 @@ mod test {
     #[test]
     fn test_remove_ring_player_runs() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRing);
+        state.set_turn(Player::White, Phase::RemoveRing);
 
         let mut run = vec![];
 
@@ -851,8 +924,7 @@ mod test {
     #[test]
     fn test_remove_ring_other_player_runs() {
         let mut state = State::new(Board::new());
-        state.current_player = Player::White;
-        state.set_phase(Phase::RemoveRing);
+        state.set_turn(Player::White, Phase::RemoveRing);
 
         let mut run = vec![];
 
@@ -883,12 +955,49 @@ mod test {
         assert_eq!(state.points_white, 1);
     }
 
+    #[test]
+    fn test_opponent_resolves_run_then_moves() {
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(HexCoord::new(-3, 0)));
+        state.board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-3, 0));
+        // the black ring jumps over a black line, flipping it into a white run
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        let white_ring = HexCoord::new(2, 3);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &white_ring);
+
+        state
+            .apply(&Action::from(MoveRing {
+                player: Player::Black,
+                from: HexCoord::new(-3, 0),
+                to: HexCoord::new(3, 0),
+            }))
+            .unwrap();
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+        assert_eq!(state.last_mover(), Some(Player::Black));
+
+        let run: Vec<HexCoord> = (-2..=2).map(|i| HexCoord::new(i, 0)).collect();
+        state.resolve_run(&run, &white_ring).unwrap();
+
+        // white scored at the start of their turn and now makes their move
+        assert_eq!(state.points_white, 1);
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+
+        state.undo();
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::RemoveRing);
+    }
+
     #[test]
     fn test_remove_ring_undo() {
         for player in [Player::White, Player::Black] {
         let mut state = State::new(Board::new());
-            state.current_player = player;
-            state.set_phase(Phase::RemoveRing);
+            state.set_turn(player, Phase::RemoveRing);
 
             let c = HexCoord::new(2, 3);
             state.board.place_unchecked(&Piece::Ring(player), &c);
@@ -1,70 +1,1130 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use macroquad::miniquad::date;
+
+use crate::core::board::Board;
 use crate::core::command::Command;
-use crate::core::{entities::*, state::*};
-use macroquad::rand::*;
+use crate::core::coord::{norm_squared, Point, Symmetry};
+use crate::core::opening_book::OpeningBook;
+use crate::core::packed::PackedMove;
+use crate::core::rng::Rng;
+use crate::core::state::StateChange;
+use crate::core::transposition::{Bound, SharedTable};
+use crate::core::{actions::Action, entities::*, state::*, zobrist};
+
+// evaluations within this margin count as an even position
+const DRAW_MARGIN: f32 = 50.;
+// terminal positions outweigh any heuristic, discounted so faster wins score higher
+const WIN_SCORE: f32 = 1e7;
+const WIN_DEPTH_BONUS: f32 = 1000.;
+// scores beyond this are wins or losses, no heuristic comes close
+const WIN_THRESHOLD: f32 = WIN_SCORE / 2.;
+// move ordering tiers, runs before flips before central moves
+const ORDER_RUN_BONUS: f32 = 1000.;
+const ORDER_FLIP_BONUS: f32 = 10.;
+// half width of the first window around the previous iteration's score
+const ASPIRATION_WINDOW: f32 = 50.;
+// out of book rings are placed looking at the opponent's reply
+const PLACEMENT_DEPTH: u32 = 1;
+// extra plies on one line at most once a player is a run from winning
+const MAX_EXTENSIONS: u32 = 2;
+
+// score and best line below each searched root move, in move order
+type ScoredMoves = Vec<(f32, Vec<Action>)>;
+// alpha and beta, None is unbounded
+type Window = (Option<f32>, Option<f32>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeuristicWeights {
+    pub markers: f32,
+    pub ring_moves: f32,
+    pub points: f32,
+    pub connected_3: f32,
+    pub connected_4: f32,
+    pub run: f32,
+    // penalty per move left to the opponent's rings, rewards hemming them in
+    pub blocking: f32,
+    // penalty per field that would complete an opponent's run, only counted against the
+    // player, own threats are left to connected_4
+    pub threats: f32,
+    // penalty per opponent marker whose flip would force a run, and the ring it costs, on the
+    // player, positive avoids such gaps in own lines. rings already given up are not charged,
+    // points values them
+    pub ring_loss: f32,
+    // scales the opponent's evaluation, above 1 values spoiling their lines over building own
+    pub defense: f32,
+    // how much worse than even a draw is valued, positive avoids draws unless behind
+    pub contempt: f32,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights {
+            markers: 1.,
+            ring_moves: 1.,
+            points: 100000.,
+            connected_3: 10.,
+            connected_4: 30.,
+            run: 1000.,
+            blocking: 0.,
+            threats: 300.,
+            ring_loss: 0.,
+            defense: 1.,
+            contempt: 0.,
+        }
+    }
+}
+
+// position evaluation from the view of player, shared with the root search threads
+pub trait Evaluator: Send + Sync {
+    fn score(&self, state: &State, player: Player) -> f32;
+
+    // score units worth being a ring ahead, scores are shown divided by it
+    fn ring_value(&self) -> f32 {
+        1.
+    }
+}
+
+// anything picking moves for the side to move, e.g. SimpleAI or core::mcts::MctsAI
+pub trait Engine {
+    // leaves state untouched, None if there is no move
+    fn choose(&mut self, state: &State) -> Option<Action>;
+}
+
+// weighted material, mobility and line counts of both players
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DefaultEvaluator {
+    pub weights: HeuristicWeights,
+}
+
+impl DefaultEvaluator {
+    pub fn new(weights: HeuristicWeights) -> Self {
+        DefaultEvaluator { weights }
+    }
+
+    fn player_score(&self, game: &State, player: Player) -> f32 {
+        let w = &self.weights;
+        let n_markers = game.board.count(&Piece::Marker(player));
+        let ring_moves = total_ring_mobility(&game.board, player);
+        let opponent_ring_moves = total_ring_mobility(&game.board, player.other());
+        let points = game.get_score(&player);
+
+        // lines two and one marker short of a run
+        let run_length = game.board.run_length();
+        let connected_3 = game.board.n_connected_markers(&player, run_length - 2);
+        let connected_4 = game.board.n_connected_markers(&player, run_length - 1);
+        let run = game.board.runs(&player).len();
+        // only worth the walk when weighted
+        let exposed = if w.ring_loss != 0. {
+            game.board.run_completing_flips(&player).len()
+        } else {
+            0
+        };
+
+        w.markers * n_markers as f32
+            + w.ring_moves * ring_moves as f32
+            + w.points * points as f32
+            + w.connected_3 * connected_3 as f32
+            + w.connected_4 * connected_4 as f32
+            + w.run * run as f32
+            - w.blocking * opponent_ring_moves as f32
+            - w.ring_loss * exposed as f32
+    }
+}
+
+fn total_ring_mobility(board: &Board, player: Player) -> usize {
+    board
+        .player_rings(player)
+        .map(|c| board.ring_mobility(c))
+        .sum()
+}
+
+impl Evaluator for DefaultEvaluator {
+    fn score(&self, state: &State, player: Player) -> f32 {
+        let threats = state.board.completing_fields(&player.other()).len();
+        self.player_score(state, player)
+            - self.weights.defense * self.player_score(state, player.other())
+            - self.weights.threats * threats as f32
+    }
+
+    fn ring_value(&self) -> f32 {
+        self.weights.points
+    }
+}
+
+// presets for SimpleAI::from_difficulty
+//   Easy:   depth 1, plays a random other move 20% of the time, less defensive
+//   Medium: depth 3, plays a random other move 5% of the time
+//   Hard:   depth 5, always plays the best move found
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn depth(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Medium => 3,
+            Difficulty::Hard => 5,
+        }
+    }
+
+    // chance of deliberately playing a random non-best move
+    pub fn blunder_chance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.2,
+            Difficulty::Medium => 0.05,
+            Difficulty::Hard => 0.,
+        }
+    }
+
+    pub fn weights(&self) -> HeuristicWeights {
+        match self {
+            Difficulty::Easy => HeuristicWeights {
+                defense: 0.5,
+                ..Default::default()
+            },
+            Difficulty::Medium => HeuristicWeights::default(),
+            Difficulty::Hard => HeuristicWeights {
+                contempt: 25.,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Personality {
+    // flips as many markers as possible, heedless of the opponent's threats
+    Flipper,
+    // builds long lines of its own
+    Builder,
+    // breaks up the opponent's lines and leaves none of its own to be completed by force
+    Defender,
+}
+
+impl Personality {
+    pub fn weights(&self) -> HeuristicWeights {
+        let default = HeuristicWeights::default();
+        match self {
+            Personality::Flipper => HeuristicWeights {
+                markers: 20.,
+                connected_3: 5.,
+                connected_4: 15.,
+                threats: 0.,
+                ..default
+            },
+            Personality::Builder => HeuristicWeights {
+                connected_3: 20.,
+                connected_4: 80.,
+                defense: 0.8,
+                ..default
+            },
+            Personality::Defender => HeuristicWeights {
+                defense: 3.,
+                ring_loss: 100.,
+                ..default
+            },
+        }
+    }
+}
+
+// plies searched below a node of each phase at most, on top of the overall depth. run and ring
+// removals are always played out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhaseDepths {
+    pub place_ring: u32,
+    pub place_marker: u32,
+    pub move_ring: u32,
+}
+
+impl Default for PhaseDepths {
+    fn default() -> Self {
+        PhaseDepths {
+            place_ring: u32::MAX,
+            place_marker: u32::MAX,
+            move_ring: u32::MAX,
+        }
+    }
+}
+
+impl PhaseDepths {
+    // only depends on the phase, so table entries of capped nodes stay valid
+    fn cap(&self, phase: &Phase, depth: u32) -> u32 {
+        match phase {
+            Phase::PlaceRing => depth.min(self.place_ring),
+            Phase::PlaceMarker => depth.min(self.place_marker),
+            Phase::MoveRing(_) => depth.min(self.move_ring),
+            _ => depth,
+        }
+    }
+}
+
+// gives up once the evaluation stayed below -threshold rings for this many searched turns in
+// a row
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resignation {
+    pub threshold: f32,
+    pub turns: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TurnOutcome {
+    Moved(Action),
+    Resigned,
+    NoMove,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    // positions searched, the same count as SimpleAI::nodes and its older evaluated_moves()
+    pub nodes: u32,
+    // deepest completed root search
+    pub depth: u32,
+    pub elapsed_secs: f64,
+    // score of the best move found, None if it was not searched
+    pub best_score: Option<f32>,
+    // searched while the opponent was thinking, the opponent played the expected reply
+    pub pondered: bool,
+}
+
+impl SearchStats {
+    pub fn nodes_per_second(&self) -> f64 {
+        if self.elapsed_secs > 0. {
+            self.nodes as f64 / self.elapsed_secs
+        } else {
+            0.
+        }
+    }
+}
+
+// a search of the position expected after the opponent's reply, running in the background
+struct Ponder {
+    hash: u64,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<(SimpleAI, Option<Action>)>,
+}
 
 pub struct SimpleAI {
     player: Player,
     max_depth: u32,
-    pub evaluated_moves: u32,
+    phase_depths: PhaseDepths,
+    evaluator: Arc<dyn Evaluator>,
+    contempt: f32,
+    table: SharedTable,
+    // set once a lazy smp helper thread should give up, the main search ended
+    stop: Option<Arc<AtomicBool>>,
+    // seconds as given by miniquad's date::now, std::time is unavailable on wasm
+    deadline: Option<f64>,
+    // positions searched per turn at most, bounds the latency on any hardware
+    max_nodes: Option<u32>,
+    aborted: bool,
+    move_ordering: bool,
+    // up to two moves per remaining depth that caused a cutoff, tried first at siblings
+    killers: Vec<[Option<Action>; 2]>,
+    killer_moves: bool,
+    // cutoffs caused by each move this turn, breaks ties in the move ordering
+    history: HashMap<PackedMove, u32>,
+    history_moves: bool,
+    aspiration: bool,
+    // search a ply deeper while a player is a run from winning
+    extensions: bool,
+    // plies added on the line being searched
+    extended: u32,
+    // double checks every generated move against the rules, only possible in debug builds
+    legality_checks: bool,
+    blunder_chance: f32,
+    // softmax temperature over the root scores in evaluation units, 0 plays the best move
+    temperature: f32,
+    // scores of the last completed root search, in move order
+    root_scores: Vec<(f32, Action)>,
+    resignation: Option<Resignation>,
+    // searched turns in a row evaluated below the resignation threshold
+    losing_turns: u32,
+    // line expected after the last turn, starting with the move played
+    pv: Vec<Action>,
+    book: Option<OpeningBook>,
+    // places rings out of book at random instead of searching, for variety
+    random_placement: bool,
+    rng: Rng,
+    // only this many root moves with the best static scores are searched, None searches all
+    beam_width: Option<usize>,
+    // root moves are split across this many threads, ignored on wasm
+    threads: usize,
+    // the threads all search the whole root sharing the table instead of splitting it
+    lazy_smp: bool,
+    // keeps searching during the opponent's turn, ignored on wasm
+    pondering: bool,
+    ponder: Option<Ponder>,
+    stats: SearchStats,
+    turn_started: f64,
+    pub nodes: u32,
 }
 
 impl SimpleAI {
     pub fn new(player: Player, max_depth: u32) -> Self {
+        SimpleAI::new_seeded(player, max_depth, macroquad::rand::rand() as u64)
+    }
+
+    // same seed and same opponent moves replay the same game
+    pub fn new_seeded(player: Player, max_depth: u32, seed: u64) -> Self {
         SimpleAI {
             max_depth,
+            phase_depths: PhaseDepths::default(),
             player,
-            evaluated_moves: 0,
+            evaluator: Arc::new(DefaultEvaluator::default()),
+            contempt: 0.,
+            table: SharedTable::new(),
+            stop: None,
+            deadline: None,
+            max_nodes: None,
+            aborted: false,
+            move_ordering: true,
+            killers: vec![],
+            killer_moves: true,
+            history: HashMap::new(),
+            history_moves: true,
+            aspiration: true,
+            extensions: true,
+            extended: 0,
+            legality_checks: true,
+            blunder_chance: 0.,
+            temperature: 0.,
+            root_scores: vec![],
+            resignation: None,
+            losing_turns: 0,
+            pv: vec![],
+            book: Some(OpeningBook::standard()),
+            random_placement: false,
+            rng: Rng::new(seed),
+            beam_width: None,
+            threads: 1,
+            lazy_smp: false,
+            pondering: false,
+            ponder: None,
+            stats: SearchStats::default(),
+            turn_started: 0.,
+            nodes: 0,
         }
     }
 
-    pub fn turn(&mut self, game: &mut State) {
-        self.evaluated_moves = 0;
-        let mut game_clone = game.clone();
-        let moves = game.legal_moves();
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    // scales better than splitting the root for deep searches, needs more than one thread
+    pub fn set_lazy_smp(&mut self, lazy_smp: bool) {
+        self.lazy_smp = lazy_smp;
+    }
+
+    // costs a thread's worth of cpu while the opponent thinks
+    pub fn set_pondering(&mut self, pondering: bool) {
+        self.pondering = pondering;
+        if !pondering {
+            self.stop_pondering();
+        }
+    }
+
+    // starts searching the position the principal variation expects once the opponent's turn
+    // is over, does nothing if the line ends before or the ai is not waiting for the opponent
+    pub fn ponder(&mut self, game: &State) {
+        self.stop_pondering();
+        if !self.pondering || cfg!(target_arch = "wasm32") || game.current_player == self.player {
+            return;
+        }
+        let mut expected = game.clone();
+        for action in self.pv.iter().skip(1) {
+            if expected.current_player == self.player || !action.is_legal(&expected) {
+                break;
+            }
+            action.execute(&mut expected);
+        }
+        let searched = expected.current_player == self.player
+            && expected.won_by().is_none()
+            && !expected.is_draw()
+            && !expected.is_forced()
+            && !expected.at_phase(&Phase::PlaceRing);
+        if !searched {
+            return;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut worker = self.worker();
+        worker.table = self.table.clone();
+        worker.stop = Some(Arc::clone(&stop));
+        let hash = zobrist::hash(&expected);
+        let thread = thread::spawn(move || {
+            worker.begin_turn();
+            let mut moves = worker.beam(&expected, root_moves(&expected));
+            let depth = worker.root_depth(&expected);
+            let best_action = worker.search_to(&expected, &mut moves, depth);
+            (worker, best_action)
+        });
+        self.ponder = Some(Ponder { hash, stop, thread });
+    }
+
+    // gives up the background search, its results stay in the table
+    pub fn stop_pondering(&mut self) {
+        if let Some(ponder) = self.ponder.take() {
+            ponder.stop.store(true, Ordering::Relaxed);
+            let _ = ponder.thread.join();
+        }
+    }
+
+    // the pondered move if the opponent played the expected reply, waits for the search to end
+    fn pondered_move(&mut self, game: &State) -> Option<Action> {
+        let ponder = self.ponder.take()?;
+        if ponder.hash != zobrist::hash(game) {
+            self.ponder = Some(ponder);
+            self.stop_pondering();
+            return None;
+        }
+        let (worker, best_action) = ponder.thread.join().ok()?;
+        best_action.as_ref()?;
+        self.nodes = worker.nodes;
+        self.pv = worker.pv;
+        self.root_scores = worker.root_scores;
+        self.stats = SearchStats {
+            pondered: true,
+            ..worker.stats
+        };
+        best_action
+    }
+
+    fn lazy_smp_threads(&self) -> usize {
+        if self.lazy_smp && !cfg!(target_arch = "wasm32") {
+            self.threads
+        } else {
+            1
+        }
+    }
+
+    // a searcher with the same settings and a table of its own for one root search thread
+    fn worker(&self) -> SimpleAI {
+        SimpleAI {
+            player: self.player,
+            max_depth: self.max_depth,
+            phase_depths: self.phase_depths,
+            evaluator: Arc::clone(&self.evaluator),
+            contempt: self.contempt,
+            table: SharedTable::new(),
+            stop: None,
+            deadline: self.deadline,
+            // the threads share what is left of the budget
+            max_nodes: self
+                .max_nodes
+                .map(|max| max.saturating_sub(self.nodes) / self.threads as u32),
+            aborted: false,
+            move_ordering: self.move_ordering,
+            killers: vec![],
+            killer_moves: self.killer_moves,
+            history: HashMap::new(),
+            history_moves: self.history_moves,
+            aspiration: self.aspiration,
+            extensions: self.extensions,
+            extended: 0,
+            legality_checks: self.legality_checks,
+            blunder_chance: 0.,
+            temperature: self.temperature,
+            root_scores: vec![],
+            resignation: None,
+            losing_turns: 0,
+            pv: vec![],
+            book: None,
+            random_placement: false,
+            rng: Rng::new(0),
+            beam_width: None,
+            threads: 1,
+            lazy_smp: false,
+            pondering: false,
+            ponder: None,
+            stats: SearchStats::default(),
+            turn_started: 0.,
+            nodes: 0,
+        }
+    }
+
+    pub fn with_weights(player: Player, max_depth: u32, weights: HeuristicWeights) -> Self {
+        SimpleAI {
+            contempt: weights.contempt,
+            ..SimpleAI::with_evaluator(player, max_depth, DefaultEvaluator::new(weights))
+        }
+    }
+
+    pub fn with_evaluator(player: Player, max_depth: u32, evaluator: impl Evaluator + 'static) -> Self {
+        SimpleAI {
+            evaluator: Arc::new(evaluator),
+            ..SimpleAI::new(player, max_depth)
+        }
+    }
+
+    pub fn from_difficulty(player: Player, difficulty: Difficulty) -> Self {
+        SimpleAI {
+            blunder_chance: difficulty.blunder_chance(),
+            ..SimpleAI::with_weights(player, difficulty.depth(), difficulty.weights())
+        }
+    }
+
+    pub fn with_personality(player: Player, max_depth: u32, personality: Personality) -> Self {
+        SimpleAI::with_weights(player, max_depth, personality.weights())
+    }
+
+    // None plays the opening from search alone
+    pub fn set_opening_book(&mut self, book: Option<OpeningBook>) {
+        self.book = book;
+    }
+
+    pub fn set_random_placement(&mut self, random: bool) {
+        self.random_placement = random;
+    }
+
+    // 0 always plays the best move, higher values play near-best moves more often
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature = temperature.max(0.);
+    }
+
+    // None never resigns
+    pub fn set_resignation(&mut self, resignation: Option<Resignation>) {
+        self.resignation = resignation;
+        self.losing_turns = 0;
+    }
+
+    // None searches every turn to the full depth
+    pub fn set_max_nodes(&mut self, max_nodes: Option<u32>) {
+        self.max_nodes = max_nodes;
+    }
+
+    pub fn set_phase_depths(&mut self, phase_depths: PhaseDepths) {
+        self.phase_depths = phase_depths;
+    }
+
+    // trades completeness for speed in fast modes, None searches every root move
+    pub fn set_beam_width(&mut self, beam_width: Option<usize>) {
+        self.beam_width = beam_width.map(|k| k.max(1));
+    }
+
+    pub fn turn(&mut self, game: &mut State) -> Option<Action> {
+        self.begin_turn();
+        let pondered = self.pondered_move(game);
+        let moves = root_moves(game);
         if game.at_phase(&Phase::PlaceRing) {
-            let index = gen_range(0, moves.len());
-            let action = moves.get(index).unwrap();
+            let placement = self.ring_placement(game, &moves);
+            return self.play(game, placement);
+        }
+        if game.is_forced() {
+            return self.play(game, moves.first().cloned());
+        }
+        let mut moves = self.beam(game, moves);
+
+        self.deadline = None;
+        self.aborted = false;
+        let best_action = pondered
+            .or_else(|| self.search_to(game, &mut moves, self.root_depth(game)))
+            .map(|action| self.sample_by_temperature(action))
+            .map(|action| self.maybe_blunder(&moves, action));
+        self.play(game, best_action)
+    }
+
+    // resigns instead of moving once the evaluation has been hopeless for long enough
+    pub fn turn_or_resign(&mut self, game: &mut State) -> TurnOutcome {
+        if let Some(resignation) = self.resignation {
+            if self.losing_turns >= resignation.turns {
+                return TurnOutcome::Resigned;
+            }
+        }
+        match self.turn(game) {
+            Some(action) => TurnOutcome::Moved(action),
+            None => TurnOutcome::NoMove,
+        }
+    }
+
+    // score of the last turn from the AI's view in rings, None if it was not searched, e.g. a
+    // book move
+    pub fn evaluation(&self) -> Option<f32> {
+        self.stats.best_score.map(|score| self.to_rings(score))
+    }
+
+    pub fn principal_variation(&self) -> &[Action] {
+        &self.pv
+    }
+
+    // statistics of the last turn
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    #[deprecated(note = "use the nodes field")]
+    pub fn evaluated_moves(&self) -> u32 {
+        self.nodes
+    }
+
+    fn begin_turn(&mut self) {
+        self.nodes = 0;
+        self.killers.clear();
+        self.history.clear();
+        self.root_scores.clear();
+        self.pv.clear();
+        self.stats = SearchStats::default();
+        self.turn_started = date::now();
+    }
+
+    // executes the chosen move, the expected line only holds if it is the searched best one
+    fn play(&mut self, game: &mut State, action: Option<Action>) -> Option<Action> {
+        self.stats.nodes = self.nodes;
+        self.stats.elapsed_secs = date::now() - self.turn_started;
+        match (self.resignation, self.stats.best_score) {
+            (Some(r), Some(score)) if self.to_rings(score) < -r.threshold => self.losing_turns += 1,
+            (_, Some(_)) => self.losing_turns = 0,
+            _ => (),
+        }
+        if let Some(action) = &action {
+            if self.pv.first() != Some(action) {
+                self.pv = vec![action.clone()];
+            }
             action.execute(game);
-            return;
         }
+        action
+    }
+
+    // deepens the search one ply at a time up to max_depth until the budget is spent, the
+    // result of the last completed depth is played
+    pub fn turn_timed(&mut self, game: &mut State, budget: Duration) -> Option<Action> {
+        self.stop_pondering();
+        self.begin_turn();
+        let moves = root_moves(game);
+        if game.at_phase(&Phase::PlaceRing) {
+            let placement = self.ring_placement(game, &moves);
+            return self.play(game, placement);
+        }
+        if game.is_forced() {
+            return self.play(game, moves.first().cloned());
+        }
+        let mut moves = self.beam(game, moves);
+
+        self.deadline = Some(date::now() + budget.as_secs_f64());
+        self.aborted = false;
+        let best_action = self.deepen(game, &mut moves, self.root_depth(game));
+        self.deadline = None;
+
+        let best_action = best_action
+            .map(|action| self.sample_by_temperature(action))
+            .map(|action| self.maybe_blunder(&moves, action));
+        self.play(game, best_action)
+    }
+
+    // deepens the search one ply at a time up to max_depth, the best move of the last completed
+    // depth once the time or node budget is spent
+    fn deepen(&mut self, game: &State, moves: &mut [Action], max_depth: u32) -> Option<Action> {
+        if self.lazy_smp_threads() > 1 {
+            return self.deepen_lazy_smp(game, moves, max_depth);
+        }
+        self.iterate(game, moves, max_depth)
+    }
+
+    // helper threads deepen the same root alongside, every other one a ply further and with
+    // the moves rotated so they spread out, their results reach the main search only through
+    // the shared table. they give up once the main search is done
+    fn deepen_lazy_smp(&mut self, game: &State, moves: &mut [Action], max_depth: u32) -> Option<Action> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (best_action, helper_nodes) = thread::scope(|s| {
+            let handles: Vec<_> = (1..self.lazy_smp_threads())
+                .map(|i| {
+                    let mut helper = self.worker();
+                    helper.table = self.table.clone();
+                    helper.stop = Some(Arc::clone(&stop));
+                    let mut helper_moves = moves.to_vec();
+                    helper_moves.rotate_left(i % moves.len().max(1));
+                    let depth = max_depth + (i % 2) as u32;
+                    s.spawn(move || {
+                        helper.iterate(game, &mut helper_moves, depth);
+                        helper.nodes
+                    })
+                })
+                .collect();
+            let best_action = self.iterate(game, moves, max_depth);
+            stop.store(true, Ordering::Relaxed);
+            let helper_nodes: u32 = handles
+                .into_iter()
+                .map(|h| h.join().expect("lazy smp thread panicked"))
+                .sum();
+            (best_action, helper_nodes)
+        });
+        self.nodes += helper_nodes;
+        best_action
+    }
 
+    // deepens on this thread alone
+    fn iterate(&mut self, game: &State, moves: &mut [Action], max_depth: u32) -> Option<Action> {
         let mut best_action = None;
-        let mut best_score: f32 = f32::NEG_INFINITY;
-        self.evaluated_moves += moves.len() as u32;
+        let mut previous_score = None;
+        for depth in 0..=max_depth {
+            // sampling needs exact scores for every root move, not ones clipped to a window
+            let result = match previous_score {
+                Some(score) if self.aspiration && self.temperature <= 0. => {
+                    self.search_root_aspirated(game, moves, depth, score)
+                }
+                _ => self.search_root(game, moves, depth),
+            };
+            match result {
+                Some((action, score)) => {
+                    // search the best move first in the next iteration
+                    if let Some(idx) = moves.iter().position(|m| *m == action) {
+                        moves[..=idx].rotate_right(1);
+                    }
+                    best_action = Some(action);
+                    previous_score = Some(score);
+                }
+                None => break,
+            }
+        }
+        // even depth 0 is cut short once extensions search past it, the best ordered move
+        // is still better than none
+        best_action.or_else(|| moves.first().cloned())
+    }
+
+    // a node budget could cut a single search short, so it is approached by deepening, as is
+    // the table lazy smp threads fill
+    fn search_to(&mut self, game: &State, moves: &mut [Action], depth: u32) -> Option<Action> {
+        if self.max_nodes.is_some() || self.lazy_smp_threads() > 1 {
+            self.deepen(game, moves, depth)
+        } else {
+            self.search_root(game, moves, depth)
+                .map(|(action, _)| action)
+        }
+    }
+
+    // the root moves with the best static evaluation after them, best first
+    fn beam(&self, game: &State, moves: Vec<Action>) -> Vec<Action> {
+        let k = match self.beam_width {
+            Some(k) if k < moves.len() => k,
+            _ => return moves,
+        };
+        let mut probe = game.clone();
+        let mut scored: Vec<(f32, Action)> = moves
+            .into_iter()
+            .map(|m| {
+                m.execute(&mut probe);
+                let score = self.evaluate(&probe);
+                m.undo(&mut probe);
+                (score, m)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        scored.into_iter().take(k).map(|(_, m)| m).collect()
+    }
+
+    // book move while in book, otherwise a shallow search favouring mobile rings
+    fn ring_placement(&mut self, game: &State, moves: &[Action]) -> Option<Action> {
+        if let Some(action) = self.book.as_ref().and_then(|book| book.lookup(game)) {
+            return Some(action);
+        }
+        if self.random_placement {
+            return match moves.len() {
+                0 => None,
+                n => Some(moves[self.rng.below(n)].clone()),
+            };
+        }
+        self.deadline = None;
+        self.aborted = false;
+        self.search_to(game, &mut distinct_placements(game, moves), PLACEMENT_DEPTH)
+    }
+
+    // samples the root moves by the softmax of their scores, the best is the most likely
+    fn sample_by_temperature(&mut self, best: Action) -> Action {
+        if self.temperature <= 0. || self.root_scores.len() < 2 {
+            return best;
+        }
+        let top = self
+            .root_scores
+            .iter()
+            .map(|(score, _)| *score)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let weights: Vec<f32> = self
+            .root_scores
+            .iter()
+            .map(|(score, _)| ((score - top) / self.temperature).exp())
+            .collect();
+        let mut pick = self.rng.next_f32() * weights.iter().sum::<f32>();
+        for (weight, (_, action)) in weights.iter().zip(&self.root_scores) {
+            if pick < *weight {
+                return action.clone();
+            }
+            pick -= weight;
+        }
+        best
+    }
+
+    // weaker presets sometimes swap the best move for a random other one
+    fn maybe_blunder(&mut self, moves: &[Action], best: Action) -> Action {
+        if moves.len() < 2 || self.blunder_chance <= 0. || self.rng.next_f32() >= self.blunder_chance {
+            return best;
+        }
+        let others: Vec<&Action> = moves.iter().filter(|m| **m != best).collect();
+        others[self.rng.below(others.len())].clone()
+    }
+
+    fn root_depth(&self, game: &State) -> u32 {
+        // run and ring removals are close to forced, a static evaluation suffices
+        match game.current_phase {
+            Phase::RemoveRun | Phase::RemoveRing => 0,
+            _ => self.phase_depths.cap(&game.current_phase, self.max_depth),
+        }
+    }
+
+    // searches a narrow window around the expected score first, a result on a bound of the
+    // window only bounds the true score, so that side is reopened and searched again
+    fn search_root_aspirated(
+        &mut self,
+        game: &State,
+        moves: &[Action],
+        depth: u32,
+        expected: f32,
+    ) -> Option<(Action, f32)> {
+        let mut window = (
+            Some(expected - ASPIRATION_WINDOW),
+            Some(expected + ASPIRATION_WINDOW),
+        );
+        loop {
+            let (action, score) = self.search_root_within(game, moves, depth, window)?;
+            match window {
+                (Some(alpha), _) if score <= alpha => window.0 = None,
+                (_, Some(beta)) if score >= beta => window.1 = None,
+                _ => return Some((action, score)),
+            }
+        }
+    }
+
+    fn search_root(&mut self, game: &State, moves: &[Action], depth: u32) -> Option<(Action, f32)> {
+        self.search_root_within(game, moves, depth, (None, None))
+    }
+
+    // best root move and its score, None if the search ran out of time. the principal
+    // variation is only replaced by completed searches
+    fn search_root_within(
+        &mut self,
+        game: &State,
+        moves: &[Action],
+        depth: u32,
+        window: Window,
+    ) -> Option<(Action, f32)> {
+        self.nodes += moves.len() as u32;
+        let parallel =
+            self.threads > 1 && !self.lazy_smp && moves.len() > 1 && !cfg!(target_arch = "wasm32");
+        let scored = if parallel {
+            self.score_moves_parallel(game, moves, depth, window)?
+        } else {
+            self.score_moves(game, moves, depth, window)?
+        };
+
+        // the first of equally scored moves wins, however the moves were split up
+        let mut best: Option<(Action, f32)> = None;
+        let mut best_pv = vec![];
+        let mut root_scores = vec![];
+        for (action, (score, line)) in moves.iter().zip(scored) {
+            root_scores.push((score, action.clone()));
+            let improves = match &best {
+                Some((_, best_score)) => score > *best_score,
+                None => true,
+            };
+            if improves {
+                best = Some((action.clone(), score));
+                best_pv = line;
+                best_pv.insert(0, action.clone());
+            }
+        }
+        if let Some((_, score)) = &best {
+            self.root_scores = root_scores;
+            self.pv = best_pv;
+            self.stats.depth = depth;
+            self.stats.best_score = Some(*score);
+        }
+        best
+    }
+
+    // each move is searched on a fresh copy of game, None if the search ran out of time.
+    // alpha rises with the best score so far, worse moves only get an upper bound, unless all
+    // scores are sampled from
+    fn score_moves(
+        &mut self,
+        game: &State,
+        moves: &[Action],
+        depth: u32,
+        (mut alpha, beta): Window,
+    ) -> Option<ScoredMoves> {
+        let mut scored = vec![];
         for action in moves {
-            action.execute(&mut game_clone);
-            let score = self.alpha_beta(&mut game_clone, self.max_depth, self.player, None, None);
-            if score > best_score {
-                best_score = score;
-                best_action = Some(action.clone());
+            let mut line = vec![];
+            let mut child = game.clone();
+            action.execute(&mut child);
+            let score = self.alpha_beta(&mut child, depth, self.player, alpha, beta, &mut line);
+            if self.aborted {
+                return None;
             }
-            action.undo(&mut game_clone);
+            if self.temperature <= 0. && alpha.is_none_or(|a| score > a) {
+                alpha = Some(score);
+            }
+            scored.push((score, line));
         }
-        if let Some(action) = best_action {
-            action.execute(game);
+        Some(scored)
+    }
+
+    // splits the moves into one contiguous chunk per thread, results keep the move order
+    fn score_moves_parallel(
+        &mut self,
+        game: &State,
+        moves: &[Action],
+        depth: u32,
+        window: Window,
+    ) -> Option<ScoredMoves> {
+        let chunk_size = moves.len().div_ceil(self.threads);
+        let results: Vec<(Option<ScoredMoves>, u32)> = thread::scope(|s| {
+            let handles: Vec<_> = moves
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let mut worker = self.worker();
+                    s.spawn(move || {
+                        let scored = worker.score_moves(game, chunk, depth, window);
+                        (scored, worker.nodes)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("root search thread panicked"))
+                .collect()
+        });
+
+        let mut scored = vec![];
+        for (chunk, evaluated) in results {
+            self.nodes += evaluated;
+            match chunk {
+                Some(chunk) => scored.extend(chunk),
+                None => self.aborted = true,
+            }
+        }
+        if self.aborted {
+            return None;
         }
+        Some(scored)
     }
 
-    fn player_heuristic(&self, game: &State, player: Player) -> f32 {
-        let n_markers = game.board.player_markers(player).count();
-        let ring_moves: usize = game
-            .board
-            .player_rings(player)
-            .map(|c| game.board.ring_targets(c).len())
-            .sum::<usize>();
-        let points = 100000 * game.get_score(&player);
+    // cheap static ordering so alpha-beta cuts off early, moves forming a run first, then
+    // by markers flipped to the mover's color, then towards the center
+    fn order_moves(&self, game: &State, moves: &[Action]) -> Vec<Action> {
+        if !self.move_ordering {
+            return moves.to_vec();
+        }
+        let player = game.current_player;
+        let mut probe = game.clone();
+        let mut scored: Vec<(f32, u32, Action)> = moves
+            .iter()
+            .map(|m| {
+                m.execute(&mut probe);
+                let run = if probe.current_phase == Phase::RemoveRun && probe.has_run(&player) {
+                    ORDER_RUN_BONUS
+                } else {
+                    0.
+                };
+                let flips: f32 = probe
+                    .last_state_change()
+                    .iter()
+                    .map(|change| match change {
+                        StateChange::MarkerFlipped(c) if probe.board.player_marker_at(c, &player) => 1.,
+                        StateChange::MarkerFlipped(_) => -1.,
+                        _ => 0.,
+                    })
+                    .sum();
+                m.undo(&mut probe);
+                let center = norm_squared(&Point::from(m.coord()));
+                let cutoffs = self.history.get(&PackedMove::from(m)).copied().unwrap_or(0);
+                (run + ORDER_FLIP_BONUS * flips - center, cutoffs, m.clone())
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.1.cmp(&a.1))
+        });
+        scored.into_iter().map(|(_, _, m)| m).collect()
+    }
+
+    fn killers_first(&self, mut moves: Vec<Action>, depth: u32) -> Vec<Action> {
+        if !self.killer_moves {
+            return moves;
+        }
+        if let Some(killers) = self.killers.get(depth as usize) {
+            for killer in killers.iter().rev().flatten() {
+                if let Some(idx) = moves.iter().position(|m| m == killer) {
+                    moves[..=idx].rotate_right(1);
+                }
+            }
+        }
+        moves
+    }
 
-        let connected_3 = 10 * game.board.n_connected_markers(&player, 3);
-        let connected_4 = 30 * game.board.n_connected_markers(&player, 4);
-        let run = 1000 * game.board.runs(&player).len();
+    fn store_cutoff(&mut self, depth: u32, m: &Action) {
+        if self.history_moves {
+            *self.history.entry(PackedMove::from(m)).or_default() += 1;
+        }
+        let depth = depth as usize;
+        if self.killers.len() <= depth {
+            self.killers.resize(depth + 1, [None, None]);
+        }
+        let slots = &mut self.killers[depth];
+        if slots[0].as_ref() != Some(m) {
+            slots[1] = slots[0].take();
+            slots[0] = Some(m.clone());
+        }
+    }
 
-        (n_markers + ring_moves + points + connected_3 + connected_4 + run) as f32
+    fn out_of_budget(&mut self) -> bool {
+        if !self.aborted {
+            self.aborted = self.deadline.is_some_and(|deadline| date::now() >= deadline)
+                || self.max_nodes.is_some_and(|max| self.nodes >= max)
+                || self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed));
+        }
+        self.aborted
+    }
+
+    // static evaluation from the AI's view in rings, nothing is searched. 1 is as good as a
+    // ring ahead, heuristic scores stay within a few rings
+    pub fn evaluate(&self, state: &State) -> f32 {
+        self.to_rings(self.evaluator.score(state, self.player))
     }
 
-    fn heuristic(&self, game: &State, player: Player) -> f32 {
-        self.player_heuristic(game, player) - self.player_heuristic(game, player.other())
+    // search scores divided by the evaluator's ring value, wins and losses are infinite. the
+    // search itself keeps the raw scores, dividing all of them would not change a decision
+    fn to_rings(&self, score: f32) -> f32 {
+        let ring_value = self.evaluator.ring_value();
+        if score.abs() >= WIN_THRESHOLD {
+            score.signum() * f32::INFINITY
+        } else if ring_value > 0. {
+            score / ring_value
+        } else {
+            score
+        }
     }
 
+    // the evaluation is compared against the value of a draw, which contempt lowers
+    pub fn accepts_draw(&self, game: &State) -> bool {
+        let score = self.evaluator.score(game, self.player) + self.contempt;
+        let late_game = game.get_score(&Player::White) + game.get_score(&Player::Black) > 0;
+        score < -DRAW_MARGIN || (late_game && score.abs() <= DRAW_MARGIN)
+    }
+
+    pub fn offers_draw(&self, game: &State) -> bool {
+        game.get_score(&self.player) < game.get_score(&self.player.other())
+            && self.accepts_draw(game)
+    }
+
+    // pv receives the best line below this node, left empty at leaves and table hits
     fn alpha_beta(
         &mut self,
         game: &mut State,
@@ -72,50 +1132,1277 @@ impl SimpleAI {
         ai_player: Player,
         alpha: Option<f32>,
         beta: Option<f32>,
+        pv: &mut Vec<Action>,
     ) -> f32 {
+        pv.clear();
         let mut alpha = alpha.unwrap_or(f32::NEG_INFINITY);
         let mut beta = beta.unwrap_or(f32::INFINITY);
 
-        if game.won_by().is_some() || depth == 0 {
-            return self.heuristic(&game, ai_player);
+        if let Some(winner) = game.won_by() {
+            // extended plies do not count, a win stays worth its distance from the root
+            let score = WIN_SCORE + WIN_DEPTH_BONUS * (depth as f32 - self.extended as f32);
+            return if winner == ai_player { score } else { -score };
+        }
+        // positions never repeat, every move adds a marker or takes a ring, so draws are
+        // blocked players under blocked_player_loses(false)
+        if game.is_draw() {
+            return -self.contempt;
         }
 
-        if game.current_player == ai_player {
-            let mut best_val = f32::NEG_INFINITY;
-            for m in game.legal_moves() {
-                self.evaluated_moves += 1;
+        let depth = self.phase_depths.cap(&game.current_phase, depth);
+        // quiescence, pending removals are played out before evaluating so a run about to be
+        // scored is not mistaken for a mere line
+        let forced = matches!(game.current_phase, Phase::RemoveRun | Phase::RemoveRing);
+        // sharp positions, a fixed depth misses the winning or saving move
+        let extend = self.extensions
+            && !forced
+            && self.extended < MAX_EXTENSIONS
+            && [Player::White, Player::Black]
+                .iter()
+                .any(|p| game.get_score(p) + 1 >= game.win_score());
+        let plies_left = depth as f32 - self.extended as f32;
+        let depth = depth + extend as u32;
+        if depth == 0 && !forced {
+            return self.evaluator.score(game, ai_player);
+        }
+        let child_depth = depth.saturating_sub(1);
 
-                if !m.is_legal(game) {
-                    panic!("ILLEGAL ACTION {:?}", m);
+        // the caller discards the whole iteration, the value does not matter. removals left at
+        // depth 0 are few and always played out, so a depth 0 search still completes
+        if depth > 0 && self.out_of_budget() {
+            return 0.;
+        }
+
+        let hash = zobrist::hash(game);
+        let probed = self.table.probe(
+            hash,
+            depth,
+            win_to_table(alpha, plies_left),
+            win_to_table(beta, plies_left),
+        );
+        if let Some(score) = probed {
+            return win_from_table(score, plies_left);
+        }
+        let (alpha_orig, beta_orig) = (alpha, beta);
+
+        let moves = game.legal_moves();
+        // nothing to search, only in set up positions, a blocked turn otherwise ends the game
+        if moves.is_empty() {
+            return self.evaluator.score(game, ai_player);
+        }
+
+        self.extended += extend as u32;
+        let best_val = if game.current_player == ai_player {
+            let mut best_val = f32::NEG_INFINITY;
+            let moves = self.order_moves(game, &moves);
+            for m in self.killers_first(moves, depth) {
+                self.nodes += 1;
+                if self.legality_checks {
+                    debug_assert_legal(&m, game);
                 }
+                let mut line = vec![];
                 m.execute(game);
-                let value = self.alpha_beta(game, depth - 1, ai_player, Some(alpha), Some(beta));
+                let value =
+                    self.alpha_beta(game, child_depth, ai_player, Some(alpha), Some(beta), &mut line);
                 m.undo(game);
 
-                best_val = best_val.max(value);
+                if value > best_val {
+                    best_val = value;
+                    set_pv(pv, &m, line);
+                }
                 alpha = alpha.max(best_val);
                 if beta <= alpha {
+                    self.store_cutoff(depth, &m);
                     break;
                 }
             }
+            best_val
+        } else {
+            let mut best_val = f32::INFINITY;
+            let moves = self.order_moves(game, &moves);
+            for m in self.killers_first(moves, depth) {
+                self.nodes += 1;
+                if self.legality_checks {
+                    debug_assert_legal(&m, game);
+                }
+                let mut line = vec![];
+                m.execute(game);
+                let value =
+                    self.alpha_beta(game, child_depth, ai_player, Some(alpha), Some(beta), &mut line);
+                m.undo(game);
+                if value < best_val {
+                    best_val = value;
+                    set_pv(pv, &m, line);
+                }
+                beta = beta.min(best_val);
+                if beta <= alpha {
+                    self.store_cutoff(depth, &m);
+                    break;
+                }
+            }
+            best_val
+        };
+        self.extended -= extend as u32;
+        if self.aborted {
             return best_val;
         }
 
-        let mut best_val = f32::INFINITY;
-        for m in game.legal_moves() {
-            self.evaluated_moves += 1;
-            if !m.is_legal(game) {
-                panic!("ILLEGAL ACTION {:?}", m);
+        let bound = if best_val <= alpha_orig {
+            Bound::Upper
+        } else if best_val >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table
+            .store(hash, depth, win_to_table(best_val, plies_left), bound);
+        best_val
+    }
+}
+
+// win scores depend on the depth left at the node, the table keeps them relative to the node
+// so they stay exact when probed with a different depth left
+fn win_to_table(score: f32, plies_left: f32) -> f32 {
+    if score >= WIN_THRESHOLD {
+        score - WIN_DEPTH_BONUS * plies_left
+    } else if score <= -WIN_THRESHOLD {
+        score + WIN_DEPTH_BONUS * plies_left
+    } else {
+        score
+    }
+}
+
+fn win_from_table(score: f32, plies_left: f32) -> f32 {
+    if score >= WIN_THRESHOLD {
+        score + WIN_DEPTH_BONUS * plies_left
+    } else if score <= -WIN_THRESHOLD {
+        score - WIN_DEPTH_BONUS * plies_left
+    } else {
+        score
+    }
+}
+
+impl Engine for SimpleAI {
+    fn choose(&mut self, state: &State) -> Option<Action> {
+        self.turn(&mut state.clone())
+    }
+}
+
+// one placement of each set the board's symmetries map onto each other, they all score the
+// same. the kept ones are the first of their set in moves
+fn distinct_placements(game: &State, moves: &[Action]) -> Vec<Action> {
+    let symmetries: Vec<Symmetry> = Symmetry::all()
+        .filter(|s| !s.is_identity() && game.board.transformed(s) == game.board)
+        .collect();
+    let mut seen = HashSet::new();
+    moves
+        .iter()
+        .filter(|m| match m {
+            Action::PlaceRing(p) => {
+                let distinct = symmetries.iter().all(|s| !seen.contains(&s.apply(&p.coord)));
+                seen.insert(p.coord);
+                distinct
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+// the first of equally scored moves is played, runs keeping others intact come first
+fn root_moves(game: &State) -> Vec<Action> {
+    match game.current_phase {
+        Phase::RemoveRun => game.best_run_choices(),
+        _ => game.legal_moves(),
+    }
+}
+
+fn set_pv(pv: &mut Vec<Action>, m: &Action, line: Vec<Action>) {
+    pv.clear();
+    pv.push(m.clone());
+    pv.extend(line);
+}
+
+// legal_moves is the single source of truth, only double check it in debug builds
+fn debug_assert_legal(m: &Action, game: &State) {
+    debug_assert!(m.is_legal(game), "ILLEGAL ACTION {:?}", m);
+}
+
+// the positions of a game the players, white first, play from state on until it is over or
+// the history holds max_plies actions. the first is state itself
+#[cfg(test)]
+pub(crate) fn self_play(mut state: State, mut players: [SimpleAI; 2], max_plies: usize) -> Vec<State> {
+    let mut positions = vec![state.clone()];
+    while state.history.len() < max_plies && !state.is_terminal() {
+        let idx = match state.current_player {
+            Player::White => 0,
+            Player::Black => 1,
+        };
+        if players[idx].turn(&mut state).is_none() {
+            break;
+        }
+        positions.push(state.clone());
+    }
+    positions
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::board::Board;
+    use crate::core::coord::HexCoord;
+    use crate::core::notation::{action_to_notation, history_to_notation};
+
+    use super::*;
+
+    fn scored_state(points_ai: usize, points_human: usize) -> State {
+        let mut state = State::new(Board::new());
+        state.set_phase(Phase::PlaceMarker);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-2, 0));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 0));
+        state.points_black = points_ai;
+        state.points_white = points_human;
+        state
+    }
+
+    fn winning_state() -> State {
+        let mut state = State::new(Board::new());
+        state.points_black = 2;
+        state.set_turn(Player::Black, Phase::PlaceMarker);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(2, 0));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(3, 3));
+        for i in -2..=1 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        // the other ring could flip a row of white markers instead
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(-3, i));
+        }
+        state.compute_runs();
+        state
+    }
+
+    #[test]
+    fn test_resigns_after_hopeless_turns() {
+        let position = |points_human| {
+            let mut state = scored_state(0, points_human);
+            state.set_turn(Player::Black, Phase::PlaceMarker);
+            // a choice of rings, so the turn is searched
+            state.board
+                .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 3));
+            state
+        };
+        let mut ai = SimpleAI::new(Player::Black, 1);
+        ai.set_resignation(Some(Resignation {
+            threshold: 1.,
+            turns: 2,
+        }));
+        // an even position in between starts the count over
+        for points_human in [2, 0, 2, 2] {
+            let outcome = ai.turn_or_resign(&mut position(points_human));
+            assert!(matches!(outcome, TurnOutcome::Moved(_)));
+            assert_eq!(ai.evaluation().unwrap() < -1., points_human == 2);
+        }
+        assert_eq!(ai.turn_or_resign(&mut position(2)), TurnOutcome::Resigned);
+
+        // disabled it plays on
+        ai.set_resignation(None);
+        let outcome = ai.turn_or_resign(&mut position(2));
+        assert!(matches!(outcome, TurnOutcome::Moved(_)));
+    }
+
+    #[test]
+    fn test_evaluate_from_own_view() {
+        let state = scored_state(2, 0);
+        let black = SimpleAI::new(Player::Black, 3);
+        let white = SimpleAI::new(Player::White, 3);
+        assert!(black.evaluate(&state) > 0.);
+        assert_eq!(black.evaluate(&state), -white.evaluate(&state));
+        assert_eq!(black.nodes, 0);
+
+        let even = scored_state(0, 0);
+        assert_eq!(black.evaluate(&even), 0.);
+        // a point ahead in an otherwise even position
+        assert_eq!(black.evaluate(&scored_state(1, 0)), 1.);
+        assert_eq!(black.to_rings(-WIN_SCORE), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_takes_immediate_win() {
+        let mut state = winning_state();
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        for _ in 0..4 {
+            if state.won_by().is_some() {
+                break;
+            }
+            assert_eq!(state.current_player, Player::Black);
+            ai.turn(&mut state);
+        }
+        assert_eq!(state.won_by(), Some(Player::Black));
+    }
+
+    #[test]
+    fn test_win_score_counts_plies() {
+        let mut state = winning_state();
+        state.board = state
+            .board
+            .clone_with_only(|p| *p != Piece::Marker(Player::White));
+        let moves = root_moves(&state);
+        let mut ai = SimpleAI::new(Player::Black, 6);
+        // ring move, run and ring removal below the root move
+        let (_, deep) = ai.search_root(&state, &moves, 6).unwrap();
+        assert_eq!(deep, WIN_SCORE + 3. * WIN_DEPTH_BONUS);
+
+        // the deeper results in the table must not make the win look any closer
+        let (_, shallow) = ai.search_root(&state, &moves, 4).unwrap();
+        assert_eq!(shallow, WIN_SCORE + WIN_DEPTH_BONUS);
+    }
+
+    fn ring_choice(weights: HeuristicWeights) -> HexCoord {
+        let from = HexCoord::new(0, -1);
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        // a white four in a row, crossed by the ring moving north
+        for i in -1..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 1));
+        }
+        // three white markers to flip when moving south east
+        for i in 1..=3 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, -1));
+        }
+
+        let mut ai = SimpleAI::with_weights(Player::Black, 0, weights);
+        match ai.turn(&mut state) {
+            Some(Action::MoveRing(m)) => m.to,
+            other => panic!("expected a ring move, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_blocking_hems_in_opponent_ring() {
+        let from = HexCoord::new(0, -1);
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 3));
+        let choose = |weights| {
+            let mut ai = SimpleAI::with_weights(Player::Black, 0, weights);
+            match ai.turn(&mut state.clone()) {
+                Some(Action::MoveRing(m)) => m.to,
+                other => panic!("expected a ring move, got {:?}", other),
+            }
+        };
+
+        // only the squeeze counts, not the own ring's freedom
+        let blocking = HeuristicWeights {
+            ring_moves: 0.,
+            blocking: 1.,
+            defense: 0.,
+            ..Default::default()
+        };
+        let to = choose(blocking);
+        // right next to the white ring, cutting off its way south
+        assert_eq!(to, HexCoord::new(0, 2));
+        assert_ne!(choose(HeuristicWeights::default()), to);
+    }
+
+    // black to move its ring from (0, 1), white a marker away from the winning run
+    fn threatened_state() -> State {
+        let from = HexCoord::new(0, 1);
+        let mut state = State::new(Board::new());
+        state.points_white = 2;
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        // the ring drops the missing marker at (3, 2) next
+        for i in -1..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 2));
+        }
+        state.board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 2));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(3, 2));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-3, -3));
+        // the tempting alternative, flipping three markers into a black four going south
+        for i in -2..=0 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, i));
+        }
+        state.compute_runs();
+        state
+    }
+
+    fn threats_left(weights: HeuristicWeights, extensions: bool) -> usize {
+        let mut game = threatened_state();
+        let mut ai = SimpleAI::with_weights(Player::Black, 0, weights);
+        ai.extensions = extensions;
+        ai.turn(&mut game);
+        game.board.completing_fields(&Player::White).len()
+    }
+
+    #[test]
+    fn test_blocks_run_threat() {
+        assert_eq!(threats_left(HeuristicWeights::default(), false), 0);
+        let greedy = HeuristicWeights {
+            threats: 0.,
+            ..Default::default()
+        };
+        assert_eq!(threats_left(greedy, false), 1);
+    }
+
+    #[test]
+    fn test_extension_sees_winning_reply() {
+        // without the threat term only the extra ply reveals white's winning run
+        let greedy = HeuristicWeights {
+            threats: 0.,
+            ..Default::default()
+        };
+        assert_eq!(threats_left(greedy, true), 0);
+
+        let mut ai = SimpleAI::with_weights(Player::Black, 0, greedy);
+        ai.turn(&mut threatened_state());
+        let extended = ai.nodes;
+        ai.extensions = false;
+        ai.turn(&mut threatened_state());
+        assert!(ai.nodes < extended);
+    }
+
+    #[test]
+    fn test_ring_loss_avoids_exposed_line() {
+        let from = HexCoord::new(0, 1);
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        // jumping the white marker at (0, 0) lengthens the black line along y = 0, but leaves
+        // the white marker at (1, 0) for any ring to flip into a black run
+        for i in [-2, -1, 2] {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        for i in 0..=1 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(3, 3));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-3, 3));
+
+        let exposed_after = |weights| {
+            let mut game = state.clone();
+            SimpleAI::with_weights(Player::Black, 0, weights).turn(&mut game);
+            game.board.run_completing_flips(&Player::Black).len()
+        };
+        assert_eq!(exposed_after(HeuristicWeights::default()), 1);
+        let careful = HeuristicWeights {
+            ring_loss: 100.,
+            ..Default::default()
+        };
+        assert_eq!(exposed_after(careful), 0);
+    }
+
+    #[test]
+    fn test_personalities_differ() {
+        assert_eq!(ring_choice(Personality::Flipper.weights()), HexCoord::new(4, -1));
+        assert_eq!(ring_choice(Personality::Defender.weights()), HexCoord::new(0, 2));
+    }
+
+    #[test]
+    fn test_difficulty_randomness() {
+        let state = State::from_notation(FIXTURE_OPENING).unwrap();
+        let choices = |mut ai: SimpleAI| {
+            (0..60)
+                .map(|_| ai.turn(&mut state.clone()).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let mut easy = SimpleAI::from_difficulty(Player::White, Difficulty::Easy);
+        assert_eq!(easy.max_depth, 1);
+        let best = easy.search_root(&state, &state.legal_moves(), 1).unwrap().0;
+        let easy_choices = choices(easy);
+        assert!(easy_choices.contains(&best));
+        assert!(easy_choices.iter().any(|m| *m != best));
+
+        // hard never deviates, same search without the randomness
+        let greedy = SimpleAI::with_weights(Player::White, 1, HeuristicWeights::default());
+        let hard = SimpleAI {
+            max_depth: 1,
+            ..SimpleAI::from_difficulty(Player::White, Difficulty::Hard)
+        };
+        assert_eq!(hard.blunder_chance, 0.);
+        let greedy_best = choices(greedy)[0].clone();
+        assert!(choices(hard).iter().all(|m| *m == greedy_best));
+    }
+
+    #[test]
+    fn test_seeded_games_replay() {
+        let play = |seed: u64| {
+            let opening = State::from_notation(FIXTURE_OPENING).unwrap();
+            let players = [
+                SimpleAI {
+                    blunder_chance: 0.3,
+                    ..SimpleAI::new_seeded(Player::White, 1, seed)
+                },
+                SimpleAI::new_seeded(Player::Black, 0, seed),
+            ];
+            let end = self_play(opening, players, 60).pop().unwrap();
+            history_to_notation(&end.history)
+        };
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7), play(8));
+    }
+
+    // only cares about having a ring on one field
+    struct RingOn(HexCoord);
+
+    impl Evaluator for RingOn {
+        fn score(&self, state: &State, player: Player) -> f32 {
+            if state.board.player_ring_at(&self.0, &player) {
+                1.
+            } else {
+                0.
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        let from = HexCoord::new(0, -1);
+        let target = HexCoord::new(-2, -1);
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(3, 3));
+        assert!(state.legal_moves().len() > 1);
+
+        let mut ai = SimpleAI::with_evaluator(Player::Black, 0, RingOn(target));
+        match ai.turn(&mut state) {
+            Some(Action::MoveRing(m)) => assert_eq!(m.to, target),
+            other => panic!("expected a ring move, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_custom_weights() {
+        // not minding the white four it could break instead
+        let marker_hungry = HeuristicWeights {
+            markers: 50.,
+            threats: 0.,
+            ..Default::default()
+        };
+        assert_eq!(ring_choice(marker_hungry), HexCoord::new(4, -1));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ILLEGAL ACTION")]
+    fn test_planted_illegal_move_caught() {
+        use crate::core::actions::PlaceMarker;
+
+        let state = scored_state(0, 0);
+        // no ring of the current player there
+        let planted = Action::from(PlaceMarker {
+            coord: HexCoord::new(-2, 0),
+        });
+        debug_assert_legal(&planted, &state);
+    }
+
+    #[test]
+    fn test_generated_moves_pass_check() {
+        let state = scored_state(0, 0);
+        let moves = state.legal_moves();
+        assert!(!moves.is_empty());
+        moves.iter().for_each(|m| debug_assert_legal(m, &state));
+    }
+
+    #[test]
+    fn test_legality_check_leaves_search_unchanged() {
+        let state = midgame_state();
+        let search = |legality_checks| {
+            let mut ai = SimpleAI::new(state.current_player, 3);
+            ai.legality_checks = legality_checks;
+            let action = ai.turn(&mut state.clone());
+            (action, ai.nodes)
+        };
+        assert_eq!(search(true), search(false));
+    }
+
+    #[test]
+    fn test_timed_turn_takes_immediate_win() {
+        let mut state = winning_state();
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        for _ in 0..4 {
+            if state.won_by().is_some() {
+                break;
+            }
+            ai.turn_timed(&mut state, Duration::from_secs(60));
+        }
+        assert_eq!(state.won_by(), Some(Player::Black));
+    }
+
+    #[test]
+    fn test_timed_turn_without_budget_still_moves() {
+        let mut state = State::from_notation(FIXTURE_OPENING).unwrap();
+        let before = state.clone();
+        let mut ai = SimpleAI::new(Player::White, 4);
+
+        let action = ai.turn_timed(&mut state, Duration::ZERO).unwrap();
+        assert!(action.is_legal(&before));
+        assert_eq!(state.history.len(), before.history.len() + 1);
+        // the aborted iteration left nothing in the table
+        assert!(ai.table.is_empty());
+    }
+
+    fn midgame_state() -> State {
+        let opening = State::from_notation(FIXTURE_OPENING).unwrap();
+        let players = [SimpleAI::new(Player::White, 0), SimpleAI::new(Player::Black, 0)];
+        let state = self_play(opening, players, 30).pop().unwrap();
+        assert_eq!(state.history.len(), 30);
+        state
+    }
+
+    #[test]
+    fn test_pondering_reuses_expected_reply() {
+        // the ai finishes its turn and ponders the reply its line expects
+        let pondering = || {
+            let mut state = midgame_state();
+            let me = state.current_player;
+            let mut ai = SimpleAI::new(me, 2);
+            ai.set_pondering(true);
+            while state.current_player == me {
+                ai.turn(&mut state).unwrap();
             }
-            m.execute(game);
-            let value = self.alpha_beta(game, depth - 1, ai_player, Some(alpha), Some(beta));
-            m.undo(game);
-            best_val = best_val.min(value);
-            beta = beta.min(best_val);
-            if beta <= alpha {
+            ai.ponder(&state);
+            assert!(ai.ponder.is_some());
+            (ai, state)
+        };
+        let (mut ai, mut state) = pondering();
+        let pv = ai.principal_variation().to_vec();
+        let me = ai.player;
+        let mut expected = state.clone();
+        for action in pv.iter().skip(1) {
+            if expected.current_player == me {
                 break;
             }
+            action.execute(&mut expected);
+        }
+        let action = ai.turn(&mut expected.clone()).unwrap();
+        assert!(action.is_legal(&expected));
+        assert!(ai.stats().pondered);
+
+        // any other reply throws the pondered search away
+        let (mut ai, _) = pondering();
+        let other = state
+            .legal_moves()
+            .into_iter()
+            .find(|m| Some(m) != pv.get(1))
+            .unwrap();
+        other.execute(&mut state);
+        while state.current_player != me {
+            state.legal_moves()[0].execute(&mut state);
+        }
+        ai.turn(&mut state).unwrap();
+        assert!(!ai.stats().pondered);
+        assert!(ai.ponder.is_none());
+    }
+
+    #[test]
+    fn test_move_ordering_prunes_more() {
+        let state = midgame_state();
+        let mut evaluated = vec![];
+        for ordering in [false, true] {
+            let mut ai = SimpleAI::new(state.current_player, 3);
+            ai.move_ordering = ordering;
+            ai.turn(&mut state.clone()).unwrap();
+            evaluated.push(ai.nodes);
+        }
+        assert!(evaluated[1] < evaluated[0], "{:?}", evaluated);
+    }
+
+    #[test]
+    fn test_quiescence_counts_pending_score() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(-1, 0);
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 3));
+        // flipping the black marker completes a white column
+        state.board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 0));
+        for i in [-2, -1, 1, 2] {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, i));
+        }
+        state
+            .apply(&Action::from(crate::core::actions::MoveRing {
+                player: Player::Black,
+                from,
+                to: HexCoord::new(1, 0),
+            }))
+            .unwrap();
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+
+        let mut ai = SimpleAI::new(Player::Black, 0);
+        let points = HeuristicWeights::default().points;
+        assert!(ai.evaluator.score(&state, Player::Black) > -points / 2.);
+        let score = ai.alpha_beta(&mut state.clone(), 0, Player::Black, None, None, &mut vec![]);
+        assert!(score < -points / 2., "{}", score);
+    }
+
+    #[test]
+    fn test_timed_removal_without_budget_still_moves() {
+        // six markers in a row, either five of them can be taken
+        let mut state = State::new(Board::new());
+        for i in -3..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        for c in [(-2, -3), (3, 3), (-3, 1)] {
+            state.board.place_unchecked(&Piece::Ring(Player::Black), &c.into());
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 3));
+        state.set_turn(Player::Black, Phase::RemoveRun);
+        state.compute_runs();
+        assert!(!state.is_forced());
+
+        let before = state.clone();
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        let action = ai.turn_timed(&mut state, Duration::ZERO).unwrap();
+        assert!(matches!(action, Action::RemoveRun(_)));
+        assert!(action.is_legal(&before));
+    }
+
+    #[test]
+    fn test_search_lets_opponent_move_after_resolving() {
+        let from = HexCoord::new(-3, 0);
+        let mut state = State::new(Board::new());
+        state.set_turn(Player::Black, Phase::MoveRing(from));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &from);
+        // the black ring jumps over a black line, flipping it into a white run
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        for c in [(2, 3), (-2, -3)] {
+            state.board.place_unchecked(&Piece::Ring(Player::White), &c.into());
+        }
+        state
+            .apply(&Action::from(crate::core::actions::MoveRing {
+                player: Player::Black,
+                from,
+                to: HexCoord::new(3, 0),
+            }))
+            .unwrap();
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+
+        // white's removals are their move, after them white places a marker, not black
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        let mut pv = vec![];
+        ai.alpha_beta(&mut state.clone(), 3, Player::Black, None, None, &mut pv);
+        assert!(matches!(
+            pv.as_slice(),
+            [Action::RemoveRun(_), Action::RemoveRing(_), Action::PlaceMarker(_)]
+        ));
+        for action in &pv[..2] {
+            state.apply(action).unwrap();
+        }
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.points_white, 1);
+    }
+
+    #[test]
+    fn test_root_score_matches_research() {
+        let state = midgame_state();
+        let player = state.current_player;
+        let mut ai = SimpleAI::new(player, 2);
+        let (best, score) = ai.search_root(&state, &root_moves(&state), 2).unwrap();
+
+        let mut after = state.clone();
+        best.execute(&mut after);
+        let mut fresh = SimpleAI::new(player, 2);
+        let research = fresh.alpha_beta(&mut after, 2, player, None, None, &mut vec![]);
+        assert_eq!(score, research);
+    }
+
+    #[test]
+    fn test_search_stats() {
+        let mut state = midgame_state();
+        let mut ai = SimpleAI::new(state.current_player, 2);
+        ai.turn(&mut state).unwrap();
+        let stats = ai.stats();
+        assert_eq!(stats.nodes, ai.nodes);
+        assert!(stats.nodes > 0);
+        assert_eq!(stats.depth, 2);
+        assert!(stats.best_score.is_some());
+        assert!(stats.elapsed_secs >= 0.);
+
+        let mut timed = SimpleAI::new(state.current_player, 3);
+        timed.turn_timed(&mut state, Duration::from_secs(60)).unwrap();
+        assert_eq!(timed.stats().depth, 3);
+    }
+
+    #[test]
+    fn test_node_budget_bounds_search() {
+        let state = midgame_state();
+        let mut full = SimpleAI::new(state.current_player, 4);
+        full.turn(&mut state.clone()).unwrap();
+
+        let mut ai = SimpleAI::new(state.current_player, 4);
+        ai.set_max_nodes(Some(500));
+        let mut game = state.clone();
+        let action = ai.turn(&mut game).unwrap();
+        assert!(ai.nodes < full.nodes);
+        assert!(ai.stats().depth < 4);
+
+        // the aborted search undid all its moves
+        let mut expected = state.clone();
+        expected.apply(&action).unwrap();
+        assert_eq!(zobrist::hash(&game), zobrist::hash(&expected));
+        assert!(game.board == expected.board);
+        assert_eq!(game.history.len(), expected.history.len());
+    }
+
+    #[test]
+    fn test_node_budget_still_removes_run() {
+        // two runs to score, the second brings black within a point of winning and extends
+        // the search past depth 0
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            for j in [0, 1] {
+                state.board
+                    .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, j));
+            }
+        }
+        for c in [(-2, -3), (3, 3), (-3, 1)] {
+            state.board.place_unchecked(&Piece::Ring(Player::Black), &c.into());
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 3));
+        state.set_turn(Player::Black, Phase::RemoveRun);
+        state.compute_runs();
+        assert!(!state.is_forced());
+
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        ai.set_max_nodes(Some(1));
+        assert!(matches!(ai.turn(&mut state.clone()), Some(Action::RemoveRun(_))));
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        let action = ai.turn_timed(&mut state.clone(), Duration::ZERO);
+        assert!(matches!(action, Some(Action::RemoveRun(_))));
+    }
+
+    #[test]
+    fn test_beam_width_limits_root_moves() {
+        let state = midgame_state();
+        let mut full = SimpleAI::new(state.current_player, 2);
+        let full_move = full.turn(&mut state.clone()).unwrap();
+
+        let mut wide = SimpleAI::new(state.current_player, 2);
+        wide.set_beam_width(Some(usize::MAX));
+        assert_eq!(wide.turn(&mut state.clone()), Some(full_move));
+        assert_eq!(wide.nodes, full.nodes);
+
+        let mut narrow = SimpleAI::new(state.current_player, 2);
+        narrow.set_beam_width(Some(1));
+        let beam = narrow.beam(&state, state.legal_moves());
+        assert_eq!(beam.len(), 1);
+        assert_eq!(narrow.turn(&mut state.clone()), beam.first().cloned());
+        assert!(narrow.nodes < full.nodes);
+    }
+
+    #[test]
+    fn test_phase_depths_cap_search() {
+        let state = midgame_state();
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        let mut plain = SimpleAI::new(state.current_player, 2);
+        let plain_move = plain.turn(&mut state.clone()).unwrap();
+
+        // marker placements are searched two plies deep, the whole search with it
+        let mut capped = SimpleAI::new(state.current_player, 4);
+        capped.set_phase_depths(PhaseDepths {
+            place_marker: 2,
+            ..Default::default()
+        });
+        let capped_move = capped.turn(&mut state.clone()).unwrap();
+        assert_eq!(capped_move, plain_move);
+        assert_eq!(capped.nodes, plain.nodes);
+
+        // capping ring moves leaves the opponent's reply unsearched
+        capped.set_phase_depths(PhaseDepths {
+            move_ring: 1,
+            ..Default::default()
+        });
+        capped.turn(&mut state.clone()).unwrap();
+        assert!(capped.nodes < plain.nodes);
+    }
+
+    #[test]
+    fn test_parallel_root_search_agrees() {
+        let state = midgame_state();
+        let mut serial = SimpleAI::new(state.current_player, 2);
+        let mut parallel = SimpleAI::new(state.current_player, 2);
+        parallel.set_threads(4);
+
+        let moves = root_moves(&state);
+        let serial_best = serial.search_root(&state, &moves, 2).unwrap();
+        let parallel_best = parallel.search_root(&state, &moves, 2).unwrap();
+        assert_eq!(parallel_best, serial_best);
+        assert_eq!(parallel.principal_variation()[0], parallel_best.0);
+    }
+
+    #[test]
+    fn test_lazy_smp_helpers_search_alongside() {
+        let state = midgame_state();
+        let mut serial = SimpleAI::new(state.current_player, 2);
+        serial.turn(&mut state.clone()).unwrap();
+
+        let mut lazy = SimpleAI::new(state.current_player, 2);
+        lazy.set_threads(3);
+        lazy.set_lazy_smp(true);
+        let action = lazy.turn(&mut state.clone()).unwrap();
+        assert!(action.is_legal(&state));
+        assert_eq!(lazy.stats().depth, 2);
+        // the helpers' nodes count towards the turn
+        assert!(lazy.nodes > serial.nodes);
+
+        // helpers share the deadline and stop with the main search
+        let mut timed = SimpleAI::new(state.current_player, 50);
+        timed.set_threads(3);
+        timed.set_lazy_smp(true);
+        let action = timed.turn_timed(&mut state.clone(), Duration::from_millis(200));
+        assert!(action.is_some_and(|a| a.is_legal(&state)));
+    }
+
+    #[test]
+    fn test_placement_prefers_mobile_fields() {
+        let mut state = State::from_notation("R e5\nR e7\nR c3\n").unwrap();
+        let mut ai = SimpleAI::new(Player::Black, 2);
+        ai.set_opening_book(None);
+        let placed = match ai.turn(&mut state).unwrap() {
+            Action::PlaceRing(a) => a.coord,
+            other => panic!("expected a ring placement, got {:?}", other),
+        };
+        let rim = HexCoord::new(-5, -4);
+        assert!(state.board.ring_mobility(&placed) > state.board.ring_mobility(&rim));
+
+        // random placement still only picks legal fields
+        let mut state = State::from_notation("R e5\nR e7\nR c3\n").unwrap();
+        let before = state.clone();
+        ai.set_random_placement(true);
+        let placed = ai.turn(&mut state).unwrap();
+        assert!(placed.is_legal(&before));
+    }
+
+    #[test]
+    fn test_symmetric_placements_searched_once() {
+        let state = State::new(Board::new());
+        let moves = state.legal_moves();
+        let distinct = distinct_placements(&state, &moves);
+        // the center and one of each set of 6 or 12 fields
+        assert_eq!(distinct.len(), 11);
+        for m in &moves {
+            let Action::PlaceRing(p) = m else { panic!() };
+            assert!(Symmetry::all().any(|s| distinct
+                .iter()
+                .any(|d| matches!(d, Action::PlaceRing(q) if s.apply(&q.coord) == p.coord))));
+        }
+
+        let mut all = SimpleAI::new(Player::White, 1);
+        let mut deduplicated = SimpleAI::new(Player::White, 1);
+        let (best, score) = all.search_root(&state, &moves, 1).unwrap();
+        let (_, distinct_score) = deduplicated.search_root(&state, &distinct, 1).unwrap();
+        assert_eq!(score, distinct_score);
+        assert!(distinct.contains(&best));
+
+        // a lone ring off center leaves only its mirror image
+        let state = State::from_notation("R e5\n").unwrap();
+        let moves = state.legal_moves();
+        assert!(distinct_placements(&state, &moves).len() < moves.len());
+        assert!(distinct_placements(&state, &moves).len() > moves.len() / 2);
+    }
+
+    #[test]
+    fn test_opening_book_can_be_disabled() {
+        let state = State::new(Board::new());
+        let mut ai = SimpleAI::new(Player::White, 3);
+        let book_move = ai.turn(&mut state.clone()).unwrap();
+        assert_eq!(book_move, OpeningBook::standard().lookup(&state).unwrap());
+
+        // a book no search would agree with
+        ai.set_opening_book(OpeningBook::parse("R a2"));
+        let book_move = ai.turn(&mut state.clone()).unwrap();
+        assert_eq!(action_to_notation(&book_move), "R a2");
+
+        ai.set_opening_book(None);
+        let searched = ai.turn(&mut state.clone()).unwrap();
+        assert!(searched.is_legal(&state));
+        assert_ne!(searched, book_move);
+    }
+
+    #[test]
+    fn test_principal_variation_is_playable() {
+        let state = midgame_state();
+        let mut ai = SimpleAI::new(state.current_player, 2);
+        let best = ai.turn(&mut state.clone()).unwrap();
+
+        let pv = ai.principal_variation().to_vec();
+        assert_eq!(pv.len(), 3);
+        assert_eq!(pv[0], best);
+        let mut replay = state.clone();
+        for action in &pv {
+            replay.apply(action).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_killer_moves_prune_more() {
+        let state = midgame_state();
+        let mut evaluated = vec![];
+        for killers in [false, true] {
+            let mut ai = SimpleAI::new(state.current_player, 4);
+            ai.killer_moves = killers;
+            ai.turn(&mut state.clone()).unwrap();
+            evaluated.push(ai.nodes);
+        }
+        assert!(evaluated[1] < evaluated[0], "{:?}", evaluated);
+    }
+
+    #[test]
+    fn test_temperature_samples_near_best() {
+        let state = midgame_state();
+        let mut greedy = SimpleAI::new_seeded(state.current_player, 2, 7);
+        let mut cold = SimpleAI::new_seeded(state.current_player, 2, 7);
+        cold.set_temperature(0.);
+        let mut warm = SimpleAI::new_seeded(state.current_player, 2, 7);
+        warm.set_temperature(20.);
+
+        let best = greedy.turn(&mut state.clone()).unwrap();
+        assert_eq!(cold.turn(&mut state.clone()).unwrap(), best);
+        assert_eq!(cold.nodes, greedy.nodes);
+        let warm_choices: Vec<Action> = (0..20)
+            .map(|_| warm.turn(&mut state.clone()).unwrap())
+            .collect();
+
+        // exact root scores, the sampled moves stay close to the best
+        let top = warm.root_scores.iter().map(|(s, _)| *s).fold(f32::MIN, f32::max);
+        assert_eq!(Some(top), greedy.stats().best_score);
+        for choice in &warm_choices {
+            let (score, _) = warm.root_scores.iter().find(|(_, m)| m == choice).unwrap();
+            assert!(top - score < 200.);
+        }
+        assert!(warm_choices.iter().any(|m| *m != best));
+        assert!(warm_choices.contains(&best));
+    }
+
+    #[test]
+    fn test_history_breaks_ordering_ties() {
+        let state = State::new(Board::new());
+        let moves = state.legal_moves();
+        let mut ai = SimpleAI::new(Player::White, 3);
+        let plain = ai.order_moves(&state, &moves);
+        assert_eq!(plain[0].coord(), HexCoord::new(0, 0));
+
+        // the last of the six fields around the center, all as central as each other
+        let favourite = plain[6].clone();
+        ai.history.insert(PackedMove::from(&favourite), 3);
+        let ordered = ai.order_moves(&state, &moves);
+        assert_eq!(ordered[0], plain[0]);
+        assert_eq!(ordered[1], favourite);
+
+        ai.turn(&mut midgame_state()).unwrap();
+        assert!(!ai.history.is_empty());
+        ai.begin_turn();
+        assert!(ai.history.is_empty());
+    }
+
+    #[test]
+    fn test_aspiration_keeps_move() {
+        let state = midgame_state();
+        let mut results = vec![];
+        for aspiration in [false, true] {
+            let mut ai = SimpleAI::new(state.current_player, 3);
+            ai.aspiration = aspiration;
+            let action = ai.turn_timed(&mut state.clone(), Duration::from_secs(600)).unwrap();
+            results.push((action, ai.nodes));
+        }
+        assert_eq!(results[0].0, results[1].0);
+        assert!(results[1].1 <= results[0].1, "{:?}", results);
+    }
+
+    #[test]
+    fn test_repeated_search_hits_table() {
+        let mut state = State::from_notation(FIXTURE_OPENING).unwrap();
+        let mut ai = SimpleAI::new(Player::White, 2);
+
+        let first = ai.turn(&mut state).unwrap();
+        let first_evaluated = ai.nodes;
+        first.undo(&mut state);
+
+        let second = ai.turn(&mut state).unwrap();
+        assert_eq!(first, second);
+        // only the root moves are expanded, their subtrees come from the table
+        assert_eq!(ai.nodes, 5);
+        assert!(ai.nodes * 10 < first_evaluated);
+    }
+
+    #[test]
+    fn test_resolves_run_greedily() {
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        for c in [(-2, -3), (3, 3), (-3, 1)] {
+            state.board.place_unchecked(&Piece::Ring(Player::Black), &c.into());
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 3));
+        state.set_turn(Player::Black, Phase::RemoveRun);
+
+        let mut ai = SimpleAI::new(Player::Black, 3);
+        assert!(matches!(ai.turn(&mut state), Some(Action::RemoveRun(_))));
+        // a lone run is taken without searching
+        assert_eq!(ai.nodes, 0);
+        assert_eq!(state.current_phase, Phase::RemoveRing);
+
+        assert!(matches!(ai.turn(&mut state), Some(Action::RemoveRing(_))));
+        assert_eq!(ai.nodes, 3);
+        assert_eq!(state.current_player, Player::White);
+    }
+
+    #[test]
+    fn test_accepts_draw_when_behind() {
+        let ai = SimpleAI::new(Player::Black, 1);
+        let state = scored_state(0, 2);
+        assert!(ai.accepts_draw(&state));
+        assert!(ai.offers_draw(&state));
+    }
+
+    #[test]
+    fn test_declines_draw_when_ahead() {
+        let ai = SimpleAI::new(Player::Black, 1);
+        let state = scored_state(2, 0);
+        assert!(!ai.accepts_draw(&state));
+        assert!(!ai.offers_draw(&state));
+    }
+
+    #[test]
+    fn test_accepts_even_draw_only_late() {
+        let ai = SimpleAI::new(Player::Black, 1);
+        assert!(!ai.accepts_draw(&scored_state(0, 0)));
+        assert!(ai.accepts_draw(&scored_state(1, 1)));
+    }
+
+    #[test]
+    fn test_contempt_declines_even_draw() {
+        let contemptuous = HeuristicWeights {
+            contempt: 200.,
+            ..Default::default()
+        };
+        let ai = SimpleAI::with_weights(Player::Black, 1, contemptuous);
+        assert!(!ai.accepts_draw(&scored_state(1, 1)));
+        // still takes a draw when clearly behind
+        assert!(ai.accepts_draw(&scored_state(0, 2)));
+
+        // a drawn node is worth less than the even position it replaces
+        let mut drawn = scored_state(1, 1);
+        drawn.set_phase(Phase::Draw);
+        let mut ai = SimpleAI::with_weights(Player::Black, 1, contemptuous);
+        assert_eq!(ai.alpha_beta(&mut drawn, 1, Player::Black, None, None, &mut vec![]), -200.);
+    }
+
+    #[test]
+    fn test_contempt_avoids_blocking_draw() {
+        // closing the last gap around black's ring draws, white is a point behind
+        let options = crate::core::options::GameOptionsBuilder::new()
+            .blocked_player_loses(false)
+            .try_build()
+            .unwrap();
+        let mut state = State::with_options(Board::new(), options);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 0));
+        for c in [(0, 1), (1, 1), (1, 0), (0, -1), (-1, -1)] {
+            state.board.place_unchecked(&Piece::Ring(Player::White), &c.into());
+        }
+        let from = HexCoord::new(-3, 0);
+        state.board.place_unchecked(&Piece::Marker(Player::White), &from);
+        state.set_turn(Player::White, Phase::MoveRing(from));
+        state.points_black = 1;
+        let gap = HexCoord::new(-1, 0);
+
+        let mut ai = SimpleAI::new(Player::White, 1);
+        let mut drawn = state.clone();
+        assert_eq!(ai.turn(&mut drawn).unwrap().coord(), gap);
+        assert!(drawn.is_draw());
+
+        // a draw worth less than two points down is avoided
+        let contemptuous = HeuristicWeights {
+            contempt: 2. * HeuristicWeights::default().points,
+            ..Default::default()
+        };
+        let mut ai = SimpleAI::with_weights(Player::White, 1, contemptuous);
+        assert_ne!(ai.turn(&mut state).unwrap().coord(), gap);
+        assert!(!state.is_terminal());
+    }
+
+    // placements are fixed so the game does not depend on the random opening
+    const FIXTURE_OPENING: &str = "R e5\nR e7\nR f7\nR g7\nR g5\nR f4\nR d8\nR d6\nR h6\nR h8\n";
+    const FIXTURE_MAX_PLIES: usize = 400;
+
+    fn fixture_path() -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ai_selfplay.txt")
+    }
+
+    fn play_fixture_game() -> String {
+        let mut state = State::from_notation(FIXTURE_OPENING).unwrap();
+        let mut white = SimpleAI::new(Player::White, 1);
+        let mut black = SimpleAI::new(Player::Black, 0);
+        while state.history.len() < FIXTURE_MAX_PLIES && state.won_by().is_none() {
+            let ai = match state.current_player {
+                Player::White => &mut white,
+                Player::Black => &mut black,
+            };
+            if ai.turn(&mut state).is_none() {
+                break;
+            }
+        }
+        let result = match state.won_by() {
+            Some(player) => format!("{:?}", player),
+            None => "none".to_string(),
+        };
+        format!("{}result {}\n", history_to_notation(&state.history), result)
+    }
+
+    // regenerate after an intended behavior change with
+    // YINSH_UPDATE_FIXTURES=1 cargo test selfplay_matches_fixture
+    #[test]
+    fn test_selfplay_matches_fixture() {
+        let played = play_fixture_game();
+        if std::env::var_os("YINSH_UPDATE_FIXTURES").is_some() {
+            std::fs::write(fixture_path(), &played).unwrap();
         }
-        return best_val;
+        let expected = std::fs::read_to_string(fixture_path()).unwrap();
+        assert!(played.starts_with(FIXTURE_OPENING));
+        assert_eq!(played, expected);
     }
 }
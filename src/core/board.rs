@@ -1,15 +1,77 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 use itertools::Itertools;
 use itertools::PeekingNext;
 
 use crate::core::coord::*;
 use crate::core::entities::*;
+use crate::core::packed::packable;
 
-#[derive(Clone)]
+const SVG_LINE_WIDTH: f32 = 0.02;
+const SVG_RING_RADIUS: f32 = 0.325;
+const SVG_RING_WIDTH: f32 = 0.15;
+const SVG_MARKER_RADIUS: f32 = 0.18;
+
+fn svg_color(player: &Player) -> &'static str {
+    match player {
+        Player::White => "#ffffff",
+        Player::Black => "#005c9b",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlacementError {
+    Occupied(HexCoord),
+    OffBoard(HexCoord),
+    RadiusTooLarge(f32),
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::Occupied(coord) => write!(f, "field {:?} is occupied", coord),
+            PlacementError::OffBoard(coord) => write!(f, "field {:?} is off the board", coord),
+            PlacementError::RadiusTooLarge(radius) => {
+                write!(f, "a board of radius {} has fields beyond -5..=5", radius)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+#[derive(Clone, PartialEq)]
 pub struct Board {
     board_map: HashMap<HexCoord, Piece>,
     radius: f32,
+    run_length: usize,
+    // pieces on the board, indexed by piece_index
+    piece_counts: [usize; 4],
+}
+
+// the radius is never NaN, boards compare as full equivalence
+impl Eq for Board {}
+
+// hashes a sorted snapshot, the map's iteration order differs between equal boards
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut pieces: Vec<(&HexCoord, &Piece)> = self.board_map.iter().collect();
+        pieces.sort_by_key(|(c, _)| **c);
+        pieces.hash(state);
+        self.radius.to_bits().hash(state);
+        self.run_length.hash(state);
+    }
+}
+
+pub(crate) fn piece_index(piece: &Piece) -> usize {
+    match piece {
+        Piece::Ring(Player::White) => 0,
+        Piece::Ring(Player::Black) => 1,
+        Piece::Marker(Player::White) => 2,
+        Piece::Marker(Player::Black) => 3,
+    }
 }
 
 impl Board {
@@ -17,26 +79,59 @@ impl Board {
         Board {
             board_map: HashMap::new(),
             radius: 4.7,
+            run_length: 5,
+            piece_counts: [0; 4],
         }
     }
 
+    // at least 2, the heuristic counts lines two markers short of a run. options built as
+    // struct literals reach here unchecked
+    pub fn with_run_length(mut self, run_length: usize) -> Self {
+        self.run_length = run_length.max(2);
+        self
+    }
+
+    pub fn run_length(&self) -> usize {
+        self.run_length
+    }
+
     pub fn get_radius(&self) -> f32 {
         self.radius
     }
 
-    pub fn board_coords(&self) -> Vec<HexCoord> {
-        let mut res = Vec::new();
-        let radius = self.radius.ceil() as i8;
-
-        for dy in -radius..=radius {
-            for dx in -radius..=radius {
-                let c = HexCoord::new(dx, dy);
-                if self.valid_coord(&c) {
-                    res.push(c)
-                }
-            }
+    // fails without changes if a piece would end up off the board or a field could not be packed
+    pub fn set_radius(&mut self, radius: f32) -> Result<(), PlacementError> {
+        if !coords_within(radius).iter().all(packable) {
+            return Err(PlacementError::RadiusTooLarge(radius));
         }
-        res
+        if let Some(coord) = self
+            .board_map
+            .keys()
+            .filter(|c| !c.is_on_board(radius))
+            .min()
+        {
+            return Err(PlacementError::OffBoard(*coord));
+        }
+        self.radius = radius;
+        Ok(())
+    }
+
+    pub fn board_coords(&self) -> Vec<HexCoord> {
+        coords_within(self.radius)
+    }
+
+    // number of fields on the longest straight line across the board
+    pub fn max_line_length(&self) -> usize {
+        let coords = self.board_coords();
+        [Direction::N, Direction::NE, Direction::SE]
+            .iter()
+            .flat_map(|dir| {
+                coords
+                    .iter()
+                    .map(move |c| c.line_iter(dir).take_while(|c| self.valid_coord(c)).count())
+            })
+            .max()
+            .unwrap_or(0)
     }
 
     pub fn closest_field_to_xy(&self, x: f32, y: f32) -> Option<(HexCoord, f32)> {
@@ -49,7 +144,7 @@ impl Board {
     }
 
     pub fn valid_coord(&self, coord: &HexCoord) -> bool {
-        coord.cartesian_sq_norm() <= num::pow(self.radius, 2)
+        coord.is_on_board(self.radius)
     }
 
     pub fn occupied(&self, coord: &HexCoord) -> Option<&Piece> {
@@ -87,6 +182,10 @@ impl Board {
             .map(|(k, _)| k)
     }
 
+    pub fn pieces(&self) -> impl Iterator<Item = (&HexCoord, &Piece)> {
+        self.board_map.iter()
+    }
+
     pub fn markers(&self) -> impl Iterator<Item = &HexCoord> {
         self.filter_board(|_, v| v.is_marker())
     }
@@ -108,16 +207,74 @@ impl Board {
     }
 
     pub fn remove(&mut self, coord: &HexCoord) -> Option<Piece> {
-        self.board_map.remove(coord)
+        let removed = self.board_map.remove(coord);
+        if let Some(piece) = &removed {
+            self.piece_counts[piece_index(piece)] -= 1;
+        }
+        removed
     }
 
     pub fn place_unchecked(&mut self, piece: &Piece, coord: &HexCoord) -> Option<Piece> {
-        self.board_map.insert(*coord, *piece)
+        let replaced = self.board_map.insert(*coord, *piece);
+        if let Some(old) = &replaced {
+            self.piece_counts[piece_index(old)] -= 1;
+        }
+        self.piece_counts[piece_index(piece)] += 1;
+        replaced
+    }
+
+    // same geometry and rules with only the pieces matching predicate, e.g. rings only
+    pub fn clone_with_only(&self, predicate: impl Fn(&Piece) -> bool) -> Board {
+        let mut board = Board {
+            board_map: HashMap::new(),
+            piece_counts: [0; 4],
+            ..self.clone()
+        };
+        for (coord, piece) in self.pieces().filter(|(_, p)| predicate(p)) {
+            board.place_unchecked(piece, coord);
+        }
+        board
+    }
+
+    // the position rotated or mirrored, the board's outline maps onto itself
+    pub fn transformed(&self, symmetry: &Symmetry) -> Board {
+        let mut board = self.clone_with_only(|_| false);
+        for (coord, piece) in self.pieces() {
+            board.place_unchecked(piece, &symmetry.apply(coord));
+        }
+        board
+    }
+
+    pub fn count(&self, piece: &Piece) -> usize {
+        self.piece_counts[piece_index(piece)]
+    }
+
+    pub fn n_rings(&self) -> usize {
+        self.count(&Piece::Ring(Player::White)) + self.count(&Piece::Ring(Player::Black))
+    }
+
+    pub fn summary_line(&self) -> String {
+        format!(
+            "W: {} rings, {} markers / B: {} rings, {} markers",
+            self.count(&Piece::Ring(Player::White)),
+            self.count(&Piece::Marker(Player::White)),
+            self.count(&Piece::Ring(Player::Black)),
+            self.count(&Piece::Marker(Player::Black)),
+        )
     }
 
-    pub fn place(&mut self, piece: &Piece, coord: &HexCoord) -> Option<Piece> {
-        assert!(self.free_board_field(coord));
-        self.place_unchecked(piece, coord)
+    pub fn place(
+        &mut self,
+        piece: &Piece,
+        coord: &HexCoord,
+    ) -> Result<Option<Piece>, PlacementError> {
+        if !self.valid_coord(coord) {
+            return Err(PlacementError::OffBoard(*coord));
+        }
+        if self.occupied(coord).is_some() {
+            return Err(PlacementError::Occupied(*coord));
+        }
+        Ok(self.place_unchecked(piece, coord))
     }
 
     fn ring_targets_in_dir(&self, from: &HexCoord, dir: &Direction) -> Vec<HexCoord> {
@@ -149,6 +306,31 @@ impl Board {
         ret
     }
 
+    // only looks at the fields towards to, cheaper than collecting all ring_targets. empty
+    // fields come first, then the markers the ring jumps, right up to to
+    pub fn ring_can_reach(&self, from: &HexCoord, to: &HexCoord) -> bool {
+        let between: Vec<HexCoord> = match from.between_iter(to) {
+            Some(iter) => iter.collect(),
+            None => return false,
+        };
+        let markers = self.markers_between(from, to);
+        let (empty, jumped) = between.split_at(between.len() - markers.len());
+        self.free_board_field(to)
+            && jumped == markers.as_slice()
+            && empty.iter().all(|c| self.occupied(c).is_none())
+    }
+
+    // steps to the first field a ring at from could land on in dir, None if trapped
+    pub fn distance_to_nearest_empty(&self, from: &HexCoord, dir: &Direction) -> Option<usize> {
+        let nearest = *self.ring_targets_in_dir(from, dir).first()?;
+        from.line_iter(dir).position(|c| c == nearest)
+    }
+
+    // number of fields the ring at from can move to
+    pub fn ring_mobility(&self, from: &HexCoord) -> usize {
+        self.ring_targets(from).len()
+    }
+
     pub fn ring_targets(&self, from: &HexCoord) -> Vec<HexCoord> {
         Direction::all()
             .into_iter()
@@ -167,24 +349,38 @@ impl Board {
             panic!("marker_run_in_dir: no marker at coord {:?}", coord);
         }
 
-        let in_dir = coord
-            .line_iter(dir)
-            .take_while(|c| self.player_marker_at(c, player));
-
-        let oppo_dir = coord
-            .line_iter(&dir.opposite())
-            .skip(1)
-            .take_while(|c| self.player_marker_at(c, player));
+        // back to where the line starts, then along all of it
+        match self.marker_row(player, coord, &dir.opposite()).last() {
+            Some(start) => start
+                .line_iter(dir)
+                .take(self.marker_span_length(start, dir))
+                .collect(),
+            None => vec![],
+        }
+    }
 
-        oppo_dir
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .chain(in_dir)
+    // markers of player in a row starting at coord, including coord itself
+    fn marker_row(&self, player: &Player, coord: &HexCoord, dir: &Direction) -> Vec<HexCoord> {
+        coord
+            .line_iter(dir)
+            .take_while(|c| self.player_marker_at(c, player))
             .collect()
     }
 
-    pub fn runs(&self, player: &Player) -> Vec<Vec<HexCoord>> {
+    // length of the same colored marker line through coord along dir in both directions
+    pub fn marker_span_length(&self, coord: &HexCoord, dir: &Direction) -> usize {
+        match self.marker_at(coord).map(|m| m.owner()) {
+            Some(player) => {
+                self.marker_row(&player, coord, dir).len()
+                    + self.marker_row(&player, coord, &dir.opposite()).len()
+                    - 1
+            }
+            None => 0,
+        }
+    }
+
+    // maximal lines of the player's markers, one per line and direction
+    fn marker_lines(&self, player: &Player) -> Vec<Vec<HexCoord>> {
         let mut ret: Vec<Vec<HexCoord>> = vec![];
 
         for dir in [Direction::N, Direction::NE, Direction::SE].iter() {
@@ -195,28 +391,141 @@ impl Board {
                 }
                 let res = self.marker_run_in_dir(player, mcoord, dir);
                 cache.extend(&res);
-                ret.extend(res.as_slice().windows(5).map(|x| x.to_vec()));
+                ret.push(res);
             }
         }
         ret
     }
 
-    pub fn n_connected_markers(&self, player: &Player, length: usize) -> usize {
-        let mut result = 0;
-        for dir in [Direction::N, Direction::NE, Direction::SE].iter() {
-            let mut cache: HashSet<HexCoord> = HashSet::new();
-            for mcoord in self.player_markers(*player) {
-                if cache.contains(mcoord) {
-                    continue;
-                }
-                let res = self.marker_run_in_dir(player, mcoord, dir);
-                cache.extend(&res);
-                if res.len() == length {
-                    result += 1;
+    pub fn windows_of_runs(
+        &self,
+        player: &Player,
+        len: usize,
+    ) -> impl Iterator<Item = Vec<HexCoord>> {
+        self.marker_lines(player).into_iter().flat_map(move |line| {
+            line.windows(len)
+                .map(|x| x.to_vec())
+                .collect::<Vec<_>>()
+        })
+    }
+
+    // sorted, the marker map has no stable iteration order
+    pub fn runs(&self, player: &Player) -> Vec<Vec<HexCoord>> {
+        // too few markers for any run, skips the scan for most of the early game
+        if self.count(&Piece::Marker(*player)) < self.run_length {
+            return vec![];
+        }
+        self.windows_of_runs(player, self.run_length).sorted().collect()
+    }
+
+    // runs that do not touch any of the excluded cells, e.g. markers already picked for removal
+    pub fn runs_excluding(&self, player: &Player, excluded: &[HexCoord]) -> Vec<Vec<HexCoord>> {
+        self.runs(player)
+            .into_iter()
+            .filter(|run| !run.iter().any(|c| excluded.contains(c)))
+            .collect()
+    }
+
+    pub fn run_is_consistent(&self, player: &Player, run: &[HexCoord]) -> bool {
+        if run.len() != self.run_length || !run.iter().all(|c| self.player_marker_at(c, player)) {
+            return false;
+        }
+        let dir = run[1] - run[0];
+        Direction::all().iter().any(|d| d.dir_vec() == dir)
+            && run.windows(2).all(|w| w[1] - w[0] == dir)
+    }
+
+    pub fn assert_run_consistent(&self, player: &Player, run: &[HexCoord]) {
+        debug_assert!(
+            self.run_is_consistent(player, run),
+            "inconsistent run for {:?}: {:?}",
+            player,
+            run
+        );
+    }
+
+    pub fn assert_runs_consistent(&self, player: &Player) {
+        if cfg!(debug_assertions) {
+            for run in self.runs(player) {
+                self.assert_run_consistent(player, &run);
+            }
+        }
+    }
+
+    // groups of adjacent markers in any direction, unlike runs not necessarily in a line
+    pub fn connected_components(&self, player: &Player) -> Vec<Vec<HexCoord>> {
+        let mut visited: HashSet<HexCoord> = HashSet::new();
+        let mut components = vec![];
+
+        for start in self.player_markers(*player).sorted() {
+            if !visited.insert(*start) {
+                continue;
+            }
+            let mut component = vec![];
+            let mut stack = vec![*start];
+            while let Some(c) = stack.pop() {
+                component.push(c);
+                for dir in Direction::all() {
+                    let n = c.neighbour(&dir);
+                    if self.player_marker_at(&n, player) && visited.insert(n) {
+                        stack.push(n);
+                    }
                 }
             }
+            component.sort();
+            components.push(component);
         }
-        result
+        components
+    }
+
+    // fields extending a line of the player's markers one short of a run to a full run, either
+    // free or holding one of the player's rings about to drop a marker there. sorted
+    pub fn completing_fields(&self, player: &Player) -> Vec<HexCoord> {
+        let short = self.run_length - 1;
+        if short < 2 || self.count(&Piece::Marker(*player)) < short {
+            return vec![];
+        }
+        let open = |c: &HexCoord| self.free_board_field(c) || self.player_ring_at(c, player);
+        self.marker_lines(player)
+            .iter()
+            .filter(|line| line.len() == short)
+            .flat_map(|line| {
+                let dir = line[1] - line[0];
+                [line[0] - dir, line[short - 1] + dir]
+            })
+            .filter(open)
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    // opponent markers completing a run of the player once flipped. any ring jumping one of
+    // them forces that run, and the ring it costs, on the player. sorted
+    pub fn run_completing_flips(&self, player: &Player) -> Vec<HexCoord> {
+        let short = self.run_length - 1;
+        if self.count(&Piece::Marker(*player)) < short {
+            return vec![];
+        }
+        let row = |c: &HexCoord, dir: &Direction| self.marker_row(player, &c.neighbour(dir), dir).len();
+        self.player_markers(player.other())
+            .filter(|c| {
+                [Direction::N, Direction::NE, Direction::SE]
+                    .iter()
+                    .any(|dir| row(c, dir) + row(c, &dir.opposite()) >= short)
+            })
+            .copied()
+            .sorted()
+            .collect()
+    }
+
+    pub fn n_connected_markers(&self, player: &Player, length: usize) -> usize {
+        if self.count(&Piece::Marker(*player)) < length {
+            return 0;
+        }
+        self.marker_lines(player)
+            .iter()
+            .filter(|line| line.len() == length)
+            .count()
     }
 
     pub fn flip_marker(&mut self, coord: &HexCoord) -> bool {
@@ -231,18 +540,71 @@ impl Board {
 
     pub fn flip_between(&mut self, start: &HexCoord, end: &HexCoord) -> Vec<HexCoord> {
         let mut res = vec![];
-        if let Some(iter) = start.between_iter(end) {
-            iter.for_each(|c| {
-                if self.flip_marker(&c) {
-                    res.push(c);
-                }
-            });
+        // a ring only ever moves along a line, anything else is a rules bug
+        debug_assert!(
+            start.dir_vec_to(end).is_some(),
+            "flip_between: {:?} and {:?} are not on a common line",
+            start,
+            end
+        );
+        for c in self.markers_between(start, end) {
+            if self.flip_marker(&c) {
+                res.push(c);
+            }
         }
         res
     }
 
+    // markers of either color strictly between two fields on a line, the ones a ring moving
+    // from start to end jumps and flips
+    fn markers_between(&self, start: &HexCoord, end: &HexCoord) -> Vec<HexCoord> {
+        match start.between_iter(end) {
+            Some(iter) => iter.filter(|c| self.marker_at(c).is_some()).collect(),
+            None => vec![],
+        }
+    }
+
+    // standalone svg of the position, scaled so the board spans the given radius
+    pub fn to_svg(&self, radius: f32) -> String {
+        let scale = radius / self.radius;
+        let size = 2. * radius + 2. * scale;
+        let to_svg_point = |pt: Point| (pt.0 * scale, -pt.1 * scale);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+            -0.5 * size,
+            -0.5 * size,
+            size,
+            size
+        );
+        for [p0, p1] in build_grid_lines(self.radius) {
+            let ((x0, y0), (x1, y1)) = (to_svg_point(p0), to_svg_point(p1));
+            svg += &format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"gray\" stroke-width=\"{}\"/>\n",
+                x0, y0, x1, y1, SVG_LINE_WIDTH * scale
+            );
+        }
+        for (coord, piece) in self.board_map.iter().sorted_by_key(|(c, _)| (c.0, c.1)) {
+            let (x, y) = to_svg_point(Point::from(*coord));
+            let color = svg_color(&piece.owner());
+            svg += &match piece {
+                Piece::Ring(_) => format!(
+                    "<circle class=\"ring\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                    x, y, SVG_RING_RADIUS * scale, color, SVG_RING_WIDTH * scale
+                ),
+                Piece::Marker(_) => format!(
+                    "<circle class=\"marker\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>\n",
+                    x, y, SVG_MARKER_RADIUS * scale, color, SVG_LINE_WIDTH * scale
+                ),
+            };
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
     pub fn clear(&mut self) {
         self.board_map.clear();
+        self.piece_counts = [0; 4];
     }
 }
 
@@ -250,6 +612,96 @@ impl Board {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_marker_span_length() {
+        let mut board = Board::new();
+        let line: Vec<HexCoord> = (-2..3).map(|y| HexCoord::new(0, y)).collect();
+        for c in line.iter() {
+            board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 3));
+
+        for c in line.iter() {
+            assert_eq!(board.marker_span_length(c, &Direction::N), 5);
+            assert_eq!(board.marker_span_length(c, &Direction::S), 5);
+            assert_eq!(board.marker_span_length(c, &Direction::NE), 1);
+        }
+        assert_eq!(board.marker_span_length(&HexCoord::new(0, 3), &Direction::N), 1);
+        assert_eq!(board.marker_span_length(&HexCoord::new(1, 0), &Direction::N), 0);
+        assert_eq!(board.runs(&Player::White), vec![line]);
+    }
+
+    #[test]
+    fn test_set_radius() {
+        let mut board = Board::new();
+        let edge = *board
+            .board_coords()
+            .iter()
+            .find(|c| !c.is_on_board(3.7))
+            .unwrap();
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 0));
+        board.place_unchecked(&Piece::Marker(Player::Black), &edge);
+
+        assert_eq!(board.set_radius(3.7), Err(PlacementError::OffBoard(edge)));
+        assert_eq!(board.get_radius(), 4.7);
+        assert_eq!(board.occupied(&edge), Some(&Piece::Marker(Player::Black)));
+
+        board.remove(&edge);
+        assert_eq!(board.set_radius(3.7), Ok(()));
+        assert!(!board.valid_coord(&edge));
+        assert!(board.player_ring_at(&HexCoord::new(0, 0), &Player::White));
+        assert!(board.board_coords().len() < Board::new().board_coords().len());
+
+        assert_eq!(board.set_radius(6.), Err(PlacementError::RadiusTooLarge(6.)));
+        assert_eq!(board.get_radius(), 3.7);
+        assert_eq!(board.set_radius(5.), Ok(()));
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        assert_eq!(Board::new().max_line_length(), 10);
+    }
+
+    #[test]
+    fn test_clone_with_only_rings() {
+        let mut board = Board::new().with_run_length(4);
+        let rings = [HexCoord::new(0, 0), HexCoord::new(2, -1)];
+        board.place_unchecked(&Piece::Ring(Player::White), &rings[0]);
+        board.place_unchecked(&Piece::Ring(Player::Black), &rings[1]);
+        for i in 1..=3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, i));
+        }
+
+        let rings_only = board.clone_with_only(|p| p.is_ring());
+        assert_eq!(rings_only.markers().count(), 0);
+        assert_eq!(rings_only.count(&Piece::Marker(Player::White)), 0);
+        assert_eq!(rings_only.n_rings(), 2);
+        assert!(rings_only.player_ring_at(&rings[0], &Player::White));
+        assert!(rings_only.player_ring_at(&rings[1], &Player::Black));
+        assert_eq!(rings_only.run_length(), 4);
+        assert_eq!(board.markers().count(), 3);
+    }
+
+    #[test]
+    fn test_board_as_map_key() {
+        let coords = [HexCoord::new(0, 0), HexCoord::new(1, -2), HexCoord::new(-3, 2)];
+        let mut a = Board::new();
+        let mut b = Board::new();
+        for c in coords {
+            a.place_unchecked(&Piece::Marker(Player::White), &c);
+        }
+        for c in coords.iter().rev() {
+            b.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        let mut modified = a.clone();
+        modified.flip_marker(&coords[0]);
+
+        let mut seen = HashSet::new();
+        assert!(seen.insert(a));
+        assert!(!seen.insert(b));
+        assert!(seen.insert(modified));
+    }
+
     #[test]
     fn test_ring_target() {
         let mut board = Board::new();
@@ -303,6 +755,145 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_ring_can_reach() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Marker(Player::White), &(1, 0).into());
+        board.place_unchecked(&Piece::Marker(Player::Black), &(2, 0).into());
+        board.place_unchecked(&Piece::Ring(Player::White), &(-1, 3).into());
+        board.place_unchecked(&Piece::Marker(Player::White), &(-1, -1).into());
+        board.place_unchecked(&Piece::Ring(Player::White), &(-1, -2).into());
+        let from = HexCoord::new(-1, 0);
+
+        // jump over two markers onto the first free field, not beyond
+        assert!(board.ring_can_reach(&from, &(0, 0).into()));
+        assert!(board.ring_can_reach(&from, &(3, 0).into()));
+        assert!(!board.ring_can_reach(&from, &(4, 0).into()));
+        // the same markers are flipped on the way
+        let flipped = board.clone().flip_between(&from, &(3, 0).into());
+        assert_eq!(flipped, vec![HexCoord::new(1, 0), HexCoord::new(2, 0)]);
+        // marker span: cannot land on a marker
+        assert!(!board.ring_can_reach(&from, &(1, 0).into()));
+        // blocked by a ring
+        assert!(board.ring_can_reach(&from, &(-1, 2).into()));
+        assert!(!board.ring_can_reach(&from, &(-1, 3).into()));
+        assert!(!board.ring_can_reach(&from, &(-1, 4).into()));
+        // marker followed by a ring
+        assert!(!board.ring_can_reach(&from, &(-1, -3).into()));
+        // not on a line, or not moving at all
+        assert!(!board.ring_can_reach(&from, &(1, 3).into()));
+        assert!(!board.ring_can_reach(&from, &from));
+
+        for to in board.board_coords() {
+            assert_eq!(
+                board.ring_can_reach(&from, &to),
+                board.ring_targets(&from).contains(&to)
+            );
+        }
+    }
+
+    #[test]
+    fn test_distance_to_nearest_empty() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Marker(Player::White), &(1, 0).into());
+        board.place_unchecked(&Piece::Marker(Player::White), &(2, 0).into());
+        board.place_unchecked(&Piece::Ring(Player::White), &(-1, 3).into());
+        board.place_unchecked(&Piece::Marker(Player::White), &(-1, -1).into());
+        board.place_unchecked(&Piece::Marker(Player::White), &(5, 4).into());
+        board.place_unchecked(&Piece::Ring(Player::White), &(-1, -2).into());
+
+        let from = HexCoord::new(-1, 0);
+        assert_eq!(board.distance_to_nearest_empty(&from, &Direction::SE), Some(1));
+        assert_eq!(board.distance_to_nearest_empty(&from, &Direction::N), Some(1));
+        // marker followed by a ring
+        assert_eq!(board.distance_to_nearest_empty(&from, &Direction::S), None);
+        // jump over two markers
+        assert_eq!(
+            board.distance_to_nearest_empty(&(0, 0).into(), &Direction::SE),
+            Some(3)
+        );
+        // marker at the boundary
+        assert_eq!(
+            board.distance_to_nearest_empty(&(4, 4).into(), &Direction::SE),
+            None
+        );
+
+        for dir in Direction::all() {
+            let targets = board.ring_targets_in_dir(&from, &dir);
+            assert_eq!(
+                board.distance_to_nearest_empty(&from, &dir).is_some(),
+                !targets.is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Ring(Player::White), &(1, 0).into());
+        board.place_unchecked(&Piece::Ring(Player::Black), &(-1, 2).into());
+        board.place_unchecked(&Piece::Ring(Player::Black), &(3, 3).into());
+        board.place_unchecked(&Piece::Marker(Player::White), &(0, 0).into());
+        board.place_unchecked(&Piece::Marker(Player::Black), &(-2, -1).into());
+
+        let svg = board.to_svg(100.);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 5);
+        assert_eq!(svg.matches("class=\"ring\"").count(), 3);
+        assert_eq!(svg.matches("class=\"marker\"").count(), 2);
+        assert_eq!(
+            svg.matches("<line").count(),
+            build_grid_lines(board.get_radius()).len()
+        );
+    }
+
+    #[test]
+    fn test_place_errors() {
+        let mut board = Board::new();
+        let c = HexCoord::new(1, 1);
+        assert_eq!(board.place(&Piece::Ring(Player::White), &c), Ok(None));
+        assert_eq!(
+            board.place(&Piece::Marker(Player::Black), &c),
+            Err(PlacementError::Occupied(c))
+        );
+        assert!(board.player_ring_at(&c, &Player::White));
+
+        let off = HexCoord::new(5, 0);
+        assert_eq!(
+            board.place(&Piece::Marker(Player::Black), &off),
+            Err(PlacementError::OffBoard(off))
+        );
+        assert!(board.occupied(&off).is_none());
+    }
+
+    #[test]
+    fn test_summary_line() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Ring(Player::White), &(1, 0).into());
+        board.place_unchecked(&Piece::Ring(Player::White), &(1, 1).into());
+        board.place_unchecked(&Piece::Ring(Player::Black), &(-1, 2).into());
+        for i in -2..=1 {
+            board.place_unchecked(&Piece::Marker(Player::White), &(i, -2).into());
+        }
+        board.place_unchecked(&Piece::Marker(Player::Black), &(-2, -3).into());
+        // replacing and flipping keep the tallies in sync
+        board.place_unchecked(&Piece::Marker(Player::Black), &(1, 1).into());
+        board.flip_marker(&(1, -2).into());
+        board.remove(&(-2, -2).into());
+
+        assert_eq!(
+            board.summary_line(),
+            "W: 1 rings, 2 markers / B: 1 rings, 3 markers"
+        );
+        assert_eq!(board.n_rings(), board.rings().count());
+
+        board.clear();
+        assert_eq!(
+            board.summary_line(),
+            "W: 0 rings, 0 markers / B: 0 rings, 0 markers"
+        );
+    }
+
     #[test]
     fn flip_marker_test() {
         let mut board = Board::new();
@@ -367,6 +958,30 @@ mod test {
         assert_eq!(board.rings().count(), 0);
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not on a common line")]
+    fn flip_between_disconnected() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(1, 0));
+        board.flip_between(&HexCoord::new(0, 0), &HexCoord::new(2, 1));
+    }
+
+    #[test]
+    fn too_few_markers_for_run() {
+        let mut board = Board::new();
+        for x in -1..3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(x, 0));
+        }
+        assert_eq!(board.count(&Piece::Marker(Player::White)), 4);
+        assert!(board.runs(&Player::White).is_empty());
+        assert_eq!(board.n_connected_markers(&Player::White, 5), 0);
+        assert_eq!(board.n_connected_markers(&Player::White, 4), 1);
+
+        let board = board.with_run_length(4);
+        assert_eq!(board.runs(&Player::White).len(), 1);
+    }
+
     #[test]
     fn find_single_run() {
         let mut board = Board::new();
@@ -549,6 +1164,154 @@ mod test {
         assert_eq!(runs_black.len(), 1);
     }
 
+    #[test]
+    fn test_connected_components() {
+        let mut board = Board::new();
+        // L-shape
+        for c in [(0, 0), (0, 1), (0, 2), (1, 0), (2, 0)] {
+            board.place_unchecked(&Piece::Marker(Player::White), &c.into());
+        }
+        assert_eq!(board.connected_components(&Player::White).len(), 1);
+        assert_eq!(board.connected_components(&Player::White)[0].len(), 5);
+
+        board.place_unchecked(&Piece::Marker(Player::Black), &(-3, -3).into());
+        board.place_unchecked(&Piece::Marker(Player::Black), &(3, -1).into());
+        // the other player's markers do not connect
+        board.place_unchecked(&Piece::Marker(Player::Black), &(1, 1).into());
+        assert_eq!(board.connected_components(&Player::Black).len(), 3);
+        assert_eq!(board.connected_components(&Player::White).len(), 1);
+    }
+
+    #[test]
+    fn test_run_length_four() {
+        let mut board = Board::new().with_run_length(4);
+        for i in 0..3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        assert!(board.runs(&Player::White).is_empty());
+
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
+        let runs = board.runs(&Player::White);
+        assert_eq!(runs.len(), 1);
+        assert!(board.run_is_consistent(&Player::White, &runs[0]));
+
+        let board = board.with_run_length(0);
+        assert_eq!(board.run_length(), 2);
+        assert_eq!(board.runs(&Player::White).len(), 3);
+    }
+
+    #[test]
+    fn test_windows_of_runs() {
+        let mut board = Board::new();
+        for i in -3..=3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        for i in -1..=3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, i));
+        }
+
+        let mut windows: Vec<_> = board.windows_of_runs(&Player::White, 5).collect();
+        let mut runs = board.runs(&Player::White);
+        windows.sort();
+        runs.sort();
+        assert_eq!(windows, runs);
+        assert_eq!(windows.len(), 4);
+
+        assert_eq!(board.windows_of_runs(&Player::White, 7).count(), 1);
+        assert_eq!(board.windows_of_runs(&Player::Black, 5).count(), 0);
+    }
+
+    #[test]
+    fn runs_excluding_removed_cells() {
+        let mut board = Board::new();
+        let line: Vec<HexCoord> = (-3..=2).map(|i| HexCoord::new(i, 0)).collect();
+        let other: Vec<HexCoord> = (0..=4).map(|i| HexCoord::new(i, 2)).collect();
+        assert!(other.iter().all(|c| board.valid_coord(c)));
+        line.iter().chain(other.iter()).for_each(|c| {
+            board.place_unchecked(&Piece::Marker(Player::White), c);
+        });
+        assert_eq!(board.runs(&Player::White).len(), 3);
+        assert_eq!(
+            board.runs_excluding(&Player::White, &[]),
+            board.runs(&Player::White)
+        );
+
+        let removed = &line[..5];
+        let remaining = board.runs_excluding(&Player::White, removed);
+        assert_eq!(remaining, vec![other.clone()]);
+        for run in remaining.iter() {
+            assert!(run.iter().all(|c| !removed.contains(c)));
+        }
+
+        // excluding the other run keeps both windows of the six-marker line
+        let remaining = board.runs_excluding(&Player::White, &other);
+        assert_eq!(remaining.len(), 2);
+    }
+
+    #[test]
+    fn run_consistency() {
+        let mut board = Board::new();
+        let line: Vec<HexCoord> = (-2..=2).map(|i| HexCoord::new(i, i)).collect();
+        line.iter().for_each(|c| {
+            board.place_unchecked(&Piece::Marker(Player::White), c);
+        });
+        board.assert_runs_consistent(&Player::White);
+        assert!(board.run_is_consistent(&Player::White, &line));
+        assert!(!board.run_is_consistent(&Player::Black, &line));
+        assert!(!board.run_is_consistent(&Player::White, &line[..4]));
+
+        let reversed: Vec<HexCoord> = line.iter().rev().cloned().collect();
+        assert!(board.run_is_consistent(&Player::White, &reversed));
+
+        // bent line of white markers
+        let mut bent = line[..4].to_vec();
+        bent.push(HexCoord::new(3, 2));
+        board.place_unchecked(&Piece::Marker(Player::White), &bent[4]);
+        assert!(!board.run_is_consistent(&Player::White, &bent));
+
+        // gap of two cells along a valid direction
+        let gapped: Vec<HexCoord> = (0..5).map(|i| HexCoord::new(0, 2 * i - 4)).collect();
+        gapped.iter().for_each(|c| {
+            board.place_unchecked(&Piece::Marker(Player::White), c);
+        });
+        assert!(!board.run_is_consistent(&Player::White, &gapped));
+    }
+
+    #[test]
+    fn test_completing_fields() {
+        let mut board = Board::new();
+        for i in -1..=2 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 2));
+        }
+        assert_eq!(
+            board.completing_fields(&Player::White),
+            vec![HexCoord::new(-2, 2), HexCoord::new(3, 2)]
+        );
+
+        // a black marker closes one end, a white ring keeps the other open
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 2));
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(3, 2));
+        assert_eq!(board.completing_fields(&Player::White), vec![HexCoord::new(3, 2)]);
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(3, 2));
+        assert!(board.completing_fields(&Player::White).is_empty());
+        assert!(board.completing_fields(&Player::Black).is_empty());
+    }
+
+    #[test]
+    fn test_run_completing_flips() {
+        let mut board = Board::new();
+        for i in [-2, -1, 1, 2] {
+            board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
+        assert_eq!(board.run_completing_flips(&Player::Black), vec![HexCoord::new(0, 0)]);
+        assert!(board.run_completing_flips(&Player::White).is_empty());
+
+        board.flip_marker(&HexCoord::new(2, 0));
+        assert!(board.run_completing_flips(&Player::Black).is_empty());
+    }
+
     #[test]
     fn find_multiple_runs() {
         let mut board = Board::new();
@@ -60,6 +60,25 @@ pub fn norm_squared(p: &Point) -> f32 {
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct Point(pub f32, pub f32);
 
+impl Point {
+    pub fn length_squared(&self) -> f32 {
+        norm_squared(self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    // the zero point stays zero
+    pub fn normalize(&self) -> Point {
+        let length = self.length();
+        if length == 0. {
+            return *self;
+        }
+        *self / length
+    }
+}
+
 impl Add for Point {
     type Output = Point;
 
@@ -135,7 +154,7 @@ impl From<HexCoord> for HexCoordF {
     }
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Copy, Clone)]
 pub struct HexCoord(pub i8, pub i8);
 
 impl HexCoord {
@@ -147,6 +166,10 @@ impl HexCoord {
         norm_squared(&self.clone().into())
     }
 
+    pub fn is_on_board(&self, radius: f32) -> bool {
+        self.cartesian_sq_norm() <= radius.powi(2)
+    }
+
     pub fn cartesian_sq_dist(&self, other: &HexCoord) -> f32 {
         (*self - *other).cartesian_sq_norm()
     }
@@ -238,6 +261,42 @@ impl HexCoord {
     }
 }
 
+// one of the twelve rotations and reflections mapping the hexagonal grid onto itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symmetry {
+    // in steps of 60 degrees, SE turns into NE
+    rotation: u8,
+    // mirrored at the NE line through the center before rotating
+    reflected: bool,
+}
+
+impl Symmetry {
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        (0..6).flat_map(|rotation| {
+            [false, true].map(move |reflected| Symmetry {
+                rotation,
+                reflected,
+            })
+        })
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0 && !self.reflected
+    }
+
+    pub fn apply(&self, coord: &HexCoord) -> HexCoord {
+        let mut c = if self.reflected {
+            HexCoord(coord.1, coord.0)
+        } else {
+            *coord
+        };
+        for _ in 0..self.rotation {
+            c = HexCoord(c.0 - c.1, c.0);
+        }
+        c
+    }
+}
+
 impl From<(i8, i8)> for HexCoord {
     fn from(t: (i8, i8)) -> Self {
         HexCoord::new(t.0, t.1)
@@ -295,11 +354,87 @@ impl Iterator for RangeIter {
     }
 }
 
+// all fields of a hexagonal board with the given radius, row by row
+pub fn coords_within(radius: f32) -> Vec<HexCoord> {
+    let r = radius.ceil() as i8;
+    (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| HexCoord::new(dx, dy)))
+        .filter(|c| c.is_on_board(radius))
+        .collect()
+}
+
+// end points of the grid lines of a hexagonal board with the given radius
+pub fn build_grid_lines(radius: f32) -> Vec<[Point; 2]> {
+    let dx: f32 = 0.5 * 3_f32.sqrt();
+    let mut res = Vec::new();
+
+    // diagonals
+    for dy in [-0.5_f32, 0.5_f32] {
+        let lambda: f32 = radius / (1. - dy.powi(2)).sqrt();
+        let (l0, l1) = ((-lambda).trunc() as i32, lambda.trunc() as i32);
+
+        for l in l0..=l1 {
+            let l = l as f32;
+            let det = (l.powi(2) * (dy.powi(2) - 1.) + radius.powi(2)).sqrt();
+            if det <= 0. {
+                continue;
+            }
+            let mut mu1 = -l * dy - det;
+            let mut mu2 = -l * dy + det;
+            if l.abs() > radius {
+                mu1 = mu1.ceil();
+                mu2 = mu2.floor();
+            } else {
+                mu1 = mu1.trunc();
+                mu2 = mu2.floor();
+            }
+
+            let vec = [Point(mu1 * dx, l + mu1 * dy), Point(mu2 * dx, l + mu2 * dy)];
+            res.push(vec);
+        }
+    }
+
+    // verticals
+    let lambda: f32 = radius * 2. / 3. * 3_f32.sqrt();
+    let (l0, l1) = ((-lambda).trunc() as i32, lambda.trunc() as i32);
+
+    for l in l0..=l1 {
+        let l = l as f32;
+        let det = (4. * radius.powi(2) - 3. * l.powi(2)).sqrt();
+        if det <= 0. {
+            continue;
+        }
+        let mut mu1 = 0.5 * (l - det);
+        let mut mu2 = 0.5 * (l + det);
+        if l.abs() > radius {
+            mu1 = mu1.ceil();
+            mu2 = mu2.floor();
+        } else {
+            mu1 = mu1.trunc();
+            mu2 = mu2.floor();
+        }
+
+        let vec = [Point(l * dx, -0.5 * l + mu1), Point(l * dx, -0.5 * l + mu2)];
+        res.push(vec);
+    }
+    res
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
 
+    #[test]
+    fn test_point_length() {
+        let pt = Point(3., 4.);
+        assert_eq!(pt.length_squared(), 25.);
+        assert_eq!(pt.length(), 5.);
+        assert_eq!(pt.normalize(), Point(0.6, 0.8));
+        assert!((pt.normalize().length() - 1.).abs() < 1e-6);
+        assert_eq!(Point(0., 0.).normalize(), Point(0., 0.));
+    }
+
     fn create_vec<T>(pos: T, dir: T, n: usize) -> Vec<HexCoord>
     where
         T: Into<HexCoord> + Copy,
@@ -313,6 +448,44 @@ mod test {
         res
     }
 
+    #[test]
+    fn test_symmetries_keep_board_and_lines() {
+        let coords = coords_within(4.7);
+        let mut images = vec![];
+        for symmetry in Symmetry::all() {
+            let mut mapped: Vec<HexCoord> = coords.iter().map(|c| symmetry.apply(c)).collect();
+            mapped.sort();
+            let mut sorted = coords.clone();
+            sorted.sort();
+            assert_eq!(mapped, sorted);
+
+            // directions map onto directions, so lines stay lines
+            let dir_vecs: Vec<HexCoord> = Direction::all().iter().map(|d| d.dir_vec()).collect();
+            for v in &dir_vecs {
+                assert!(dir_vecs.contains(&symmetry.apply(v)));
+            }
+            images.push(symmetry.apply(&HexCoord::new(1, 3)));
+        }
+        images.sort();
+        images.dedup();
+        assert_eq!(images.len(), 12);
+        assert!(Symmetry::all().next().unwrap().is_identity());
+    }
+
+    #[test]
+    fn test_is_on_board() {
+        let radius = 4.7;
+        assert!(HexCoord::new(0, 0).is_on_board(radius));
+        // rim cells
+        assert!(HexCoord::new(0, 4).is_on_board(radius));
+        assert!(HexCoord::new(4, 5).is_on_board(radius));
+        assert!(HexCoord::new(-4, -1).is_on_board(radius));
+        // corners of the hexagon are cut off
+        assert!(!HexCoord::new(0, 5).is_on_board(radius));
+        assert!(!HexCoord::new(5, 5).is_on_board(radius));
+        assert!(!HexCoord::new(5, 0).is_on_board(radius));
+    }
+
     #[test]
     fn test_line_iter() {
         let cases = vec![
@@ -13,7 +13,7 @@ impl Player {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
 pub enum Piece {
     Ring(Player),
     Marker(Player),
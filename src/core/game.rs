@@ -1,19 +1,61 @@
+use crate::core::actions::Action;
 use crate::core::coord::*;
 use crate::core::ai::*;
 use crate::core::board::*;
 use crate::core::command::*;
 use crate::core::entities::*;
+use crate::core::options::GameOptions;
+use crate::core::recorder::*;
 use crate::core::state::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiAction {
     ActionAtCoord(HexCoord),
     Undo,
+    Redo,
     AnimationFinished,
     AnimationInProgress,
     Idle,
     Busy,
     Restart,
+    OfferDraw,
+    AcceptDraw,
+    SelectPlayer(Player),
+    SelectDifficulty(Difficulty),
+    SelectOptions(GameOptions),
+    StartGame,
+}
+
+// everything picked before a game starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    pub human_player: Player,
+    pub difficulty: Difficulty,
+    pub options: GameOptions,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            human_player: Player::White,
+            difficulty: Difficulty::Hard,
+            options: GameOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Won(Player),
+    Draw,
+}
+
+#[derive(Debug, Clone)]
+pub enum StepResult {
+    NoChange,
+    HumanMoved,
+    AiMoved(Action),
+    GameOver(GameResult),
 }
 
 pub trait View {
@@ -27,21 +69,68 @@ pub struct Game {
     human_player: Player,
     current_player: Player,
     ai: SimpleAI,
+    recorder: Option<MoveRecorder>,
+    recording_error: Option<std::io::Error>,
 }
 
 impl Game {
     pub fn new(human_player: Player, view: Box<dyn View>, board: Board, ai_depth: u32) -> Self {
+        let ai = SimpleAI::new(human_player.other(), ai_depth);
+        Game::with_state(human_player, view, State::new(board), ai)
+    }
+
+    pub fn from_config(view: Box<dyn View>, board: Board, config: GameConfig) -> Self {
+        let ai = SimpleAI::from_difficulty(config.human_player.other(), config.difficulty);
+        Game::with_state(
+            config.human_player,
+            view,
+            State::with_options(board, config.options),
+            ai,
+        )
+    }
+
+    fn with_state(human_player: Player, view: Box<dyn View>, state: State, ai: SimpleAI) -> Self {
         let mut game = Game {
-            state: State::new(board),
+            state,
             view,
             human_player,
             current_player: human_player,
-            ai: SimpleAI::new(human_player.other(), ai_depth),
+            ai,
+            recorder: None,
+            recording_error: None,
         };
         game.view.request_update();
         game
     }
 
+    pub fn with_recorder(mut self, recorder: MoveRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn with_pondering(mut self, pondering: bool) -> Self {
+        self.ai.set_pondering(pondering);
+        self
+    }
+
+    fn record_moves(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            let mut res = recorder.record(&self.state.history);
+            if res.is_ok() && self.state.is_terminal() {
+                res = recorder.flush();
+            }
+            if let Err(err) = res {
+                self.recording_error = Some(err);
+                self.recorder = None;
+            }
+        }
+    }
+
+    // why recording stopped, the game goes on without it
+    pub fn recording_error(&self) -> Option<&std::io::Error> {
+        self.recording_error.as_ref()
+    }
+
     fn execute_for_coord(&mut self, coord: &HexCoord) -> bool {
         let some_move = self
             .state
@@ -58,42 +147,241 @@ impl Game {
         false
     }
 
+    // takes back the AI reply and the whole previous human turn, leaving the human at the
+    // start of a turn instead of halfway through e.g. a ring move
+    fn undo_human_turn(&mut self) -> bool {
+        let mut human_undone = false;
+        loop {
+            // undoing restores the player who made the action
+            let mut probe = self.state.clone();
+            if !probe.undo() {
+                break;
+            }
+            let actor = probe.player();
+            if human_undone && actor != self.human_player {
+                break;
+            }
+            self.state = probe;
+            human_undone |= actor == self.human_player;
+        }
+        human_undone
+    }
+
+    // replays what the last undo took back, the human turn and the AI reply to it
+    fn redo_human_turn(&mut self) -> bool {
+        let mut redone = false;
+        let mut ai_redone = false;
+        while !(ai_redone && self.state.player() == self.human_player) {
+            let actor = self.state.player();
+            if !self.state.redo() {
+                break;
+            }
+            redone = true;
+            ai_redone |= actor != self.human_player;
+        }
+        redone
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        match self.state.won_by() {
+            Some(player) => Some(GameResult::Won(player)),
+            None if self.state.is_draw() => Some(GameResult::Draw),
+            None => None,
+        }
+    }
+
     pub fn tick(&mut self) {
+        self.step();
+    }
+
+    pub fn step(&mut self) -> StepResult {
         let ui_action = self.view.tick(&mut self.state);
 
         if ui_action == UiAction::Busy {
-            return;
+            return StepResult::NoChange;
         }
 
-        if self.state.won_by().is_none() && self.current_player == self.human_player.other() {
-            self.ai.turn(&mut self.state);
+        let mut ai_action = None;
+        if self.result().is_none() && self.current_player == self.human_player.other() {
+            match self.ai.turn_or_resign(&mut self.state) {
+                TurnOutcome::Moved(action) => ai_action = Some(action),
+                TurnOutcome::Resigned => self.state.resign(&self.human_player.other()),
+                TurnOutcome::NoMove => (),
+            }
+            if self.ai.offers_draw(&self.state) {
+                self.state.offer_draw(&self.human_player.other());
+            }
+            // the turn is over, think about the expected reply meanwhile
+            if self.state.player() == self.human_player {
+                self.ai.ponder(&self.state);
+            }
             self.view.request_update();
         }
 
         // ensure that the last move for the current player is rendered
-        self.current_player = self.state.current_player;
+        self.current_player = self.state.player();
 
         let successful_action = match ui_action {
             UiAction::ActionAtCoord(coord) => self.execute_for_coord(&coord),
             UiAction::Undo => {
-                let p = self.state.current_player;
-                loop {
-                    let ret = self.state.undo();
-                    if !ret || self.state.current_player == p {
-                        break;
-                    }
-                }
-                true
+                self.ai.stop_pondering();
+                self.undo_human_turn()
+            }
+            UiAction::Redo => {
+                self.ai.stop_pondering();
+                self.redo_human_turn()
             }
             UiAction::Restart => {
+                self.ai.stop_pondering();
                 self.state.restart();
                 true
             }
+            UiAction::OfferDraw => {
+                self.state.offer_draw(&self.human_player);
+                if self.ai.accepts_draw(&self.state) {
+                    self.state.accept_draw(&self.human_player.other());
+                } else {
+                    self.state.decline_draw();
+                }
+                true
+            }
+            UiAction::AcceptDraw => self.state.accept_draw(&self.human_player),
             _ => false,
         };
 
         if successful_action {
             self.view.request_update();
-        } 
+        }
+        self.record_moves();
+
+        match (self.result(), ai_action) {
+            (Some(result), _) => StepResult::GameOver(result),
+            (None, Some(action)) => StepResult::AiMoved(action),
+            (None, None) if successful_action => StepResult::HumanMoved,
+            _ => StepResult::NoChange,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct ScriptedView {
+        actions: Vec<UiAction>,
+    }
+
+    impl View for ScriptedView {
+        fn request_update(&mut self) {}
+
+        fn tick(&mut self, _state: &State) -> UiAction {
+            if self.actions.is_empty() {
+                UiAction::Idle
+            } else {
+                self.actions.remove(0)
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_reports_ai_move() {
+        let view = ScriptedView {
+            actions: vec![UiAction::ActionAtCoord(HexCoord::new(0, 0))],
+        };
+        let mut game = Game::new(Player::White, Box::new(view), Board::new(), 1);
+
+        assert!(matches!(game.step(), StepResult::HumanMoved));
+        // the human move is rendered for one frame before the AI replies
+        assert!(matches!(game.step(), StepResult::NoChange));
+        match game.step() {
+            StepResult::AiMoved(Action::PlaceRing(action)) => {
+                assert!(game.state.board.player_ring_at(&action.coord, &Player::Black));
+            }
+            other => panic!("expected an AI move, got {:?}", other),
+        }
+        assert!(matches!(game.step(), StepResult::NoChange));
+    }
+
+    #[test]
+    fn test_undo_skips_ai_reply_and_human_turn() {
+        let opening = "R e5\nR e7\nR f7\nR g7\nR g5\nR f4\nR d8\nR d6\nR h6\nR h8\n";
+        let state = State::from_notation(opening).unwrap();
+        let ring = state.legal_moves()[0].coord();
+        let mut probe = state.clone();
+        probe.legal_moves()[0].execute(&mut probe);
+        let target = probe.legal_moves()[0].coord();
+
+        let mut actions = vec![UiAction::ActionAtCoord(ring), UiAction::ActionAtCoord(target)];
+        actions.extend(vec![UiAction::Idle; 6]);
+        actions.push(UiAction::Undo);
+        let n_actions = actions.len();
+
+        let view = ScriptedView { actions };
+        let mut game = Game::new(Player::White, Box::new(view), Board::new(), 0);
+        game.state = state.clone();
+
+        for _ in 0..n_actions - 1 {
+            game.step();
+        }
+        // the AI replied with a marker placement and a ring move
+        assert!(game.state.history.len() >= 14);
+        assert_eq!(game.state.player(), Player::White);
+
+        game.step();
+        assert_eq!(game.state.history, state.history);
+        assert_eq!(game.state.player(), Player::White);
+        assert_eq!(game.state.phase(), Phase::PlaceMarker);
+    }
+
+    #[test]
+    fn test_recording_error_stops_recorder() {
+        let path = std::env::temp_dir().join("yinsh_game_recorder_test.txt");
+        let opening = "R e5\nR e7\nR f7\nR g7\nR g5\nR f4\nR d8\nR d6\nR h6\nR h8\n";
+        let view = ScriptedView {
+            actions: vec![UiAction::Idle, UiAction::Undo],
+        };
+        let mut game = Game::new(Player::White, Box::new(view), Board::new(), 0)
+            .with_recorder(MoveRecorder::new(&path).unwrap());
+        game.state = State::from_notation(opening).unwrap();
+
+        game.step();
+        assert!(game.recording_error().is_none());
+        // rewriting the log after the undo needs the file
+        std::fs::remove_file(&path).unwrap();
+        game.step();
+        assert!(game.recording_error().is_some());
+        assert!(game.recorder.is_none());
+    }
+
+    #[test]
+    fn test_redo_replays_undone_turn() {
+        let opening = "R e5\nR e7\nR f7\nR g7\nR g5\nR f4\nR d8\nR d6\nR h6\nR h8\n";
+        let state = State::from_notation(opening).unwrap();
+        let ring = state.legal_moves()[0].coord();
+        let mut probe = state.clone();
+        probe.legal_moves()[0].execute(&mut probe);
+        let target = probe.legal_moves()[0].coord();
+
+        let mut actions = vec![UiAction::ActionAtCoord(ring), UiAction::ActionAtCoord(target)];
+        actions.extend(vec![UiAction::Idle; 6]);
+        actions.extend(vec![UiAction::Undo, UiAction::Redo, UiAction::Redo]);
+        let n_actions = actions.len();
+
+        let view = ScriptedView { actions };
+        let mut game = Game::new(Player::White, Box::new(view), Board::new(), 0);
+        game.state = state;
+
+        for _ in 0..n_actions - 3 {
+            game.step();
+        }
+        let played = game.state.clone();
+        game.step();
+        assert!(game.state.history.len() < played.history.len());
+
+        // the human turn and the AI reply come back together, there is nothing more to redo
+        assert!(matches!(game.step(), StepResult::HumanMoved));
+        assert!(game.state == played);
+        assert!(matches!(game.step(), StepResult::NoChange));
+        assert!(game.state == played);
     }
 }
@@ -0,0 +1,210 @@
+use crate::core::actions::Action;
+use crate::core::ai::Engine;
+use crate::core::command::Command;
+use crate::core::entities::Player;
+use crate::core::rng::Rng;
+use crate::core::state::State;
+
+// the usual UCT exploration constant, sqrt(2)
+const EXPLORATION: f32 = std::f32::consts::SQRT_2;
+// random games are cut off here and decided by points, a full board takes about this long
+const MAX_PLAYOUT_PLIES: usize = 200;
+
+struct Node {
+    // the move leading here and who made it, None at the root
+    action: Option<Action>,
+    mover: Player,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Action>,
+    visits: u32,
+    // playout results from the mover's view, 1 per win and 0.5 per draw
+    wins: f32,
+}
+
+// monte carlo tree search with UCT selection and random playouts
+pub struct MctsAI {
+    iterations: u32,
+    rng: Rng,
+}
+
+impl MctsAI {
+    pub fn new(iterations: u32) -> Self {
+        MctsAI::new_seeded(iterations, macroquad::rand::rand() as u64)
+    }
+
+    pub fn new_seeded(iterations: u32, seed: u64) -> Self {
+        MctsAI {
+            iterations,
+            rng: Rng::new(seed),
+        }
+    }
+
+    // the most visited root move, None if the game is over
+    pub fn choose_move(&mut self, state: &State) -> Option<Action> {
+        let moves = state.legal_moves();
+        if moves.len() < 2 {
+            return moves.into_iter().next();
+        }
+
+        let mut tree = vec![Node {
+            action: None,
+            mover: state.current_player.other(),
+            parent: None,
+            children: vec![],
+            untried: moves,
+            visits: 0,
+            wins: 0.,
+        }];
+        for _ in 0..self.iterations {
+            let mut game = state.clone();
+            let leaf = self.select_and_expand(&mut tree, &mut game);
+            let winner = self.playout(&mut game);
+            backpropagate(&mut tree, leaf, winner);
+        }
+
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&c| tree[c].visits)
+            .and_then(|&c| tree[c].action.clone())
+    }
+
+    // walks down the fully expanded part of the tree, then adds one untried move
+    fn select_and_expand(&mut self, tree: &mut Vec<Node>, game: &mut State) -> usize {
+        let mut node = 0;
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            node = best_child(tree, node);
+            if let Some(action) = &tree[node].action {
+                action.execute(game);
+            }
+        }
+        if tree[node].untried.is_empty() {
+            return node;
+        }
+
+        let idx = self.rng.below(tree[node].untried.len());
+        let action = tree[node].untried.swap_remove(idx);
+        let mover = game.current_player;
+        action.execute(game);
+        tree.push(Node {
+            action: Some(action),
+            mover,
+            parent: Some(node),
+            children: vec![],
+            untried: game.legal_moves(),
+            visits: 0,
+            wins: 0.,
+        });
+        let child = tree.len() - 1;
+        tree[node].children.push(child);
+        child
+    }
+
+    // plays random moves to the end, None is a draw
+    fn playout(&mut self, game: &mut State) -> Option<Player> {
+        for _ in 0..MAX_PLAYOUT_PLIES {
+            let moves = game.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            moves[self.rng.below(moves.len())].execute(game);
+        }
+        if let Some(winner) = game.won_by() {
+            return Some(winner);
+        }
+        let (white, black) = game.scores();
+        match white.cmp(&black) {
+            std::cmp::Ordering::Greater => Some(Player::White),
+            std::cmp::Ordering::Less => Some(Player::Black),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+impl Engine for MctsAI {
+    fn choose(&mut self, state: &State) -> Option<Action> {
+        self.choose_move(state)
+    }
+}
+
+fn best_child(tree: &[Node], node: usize) -> usize {
+    let parent_visits = (tree[node].visits.max(1) as f32).ln();
+    let uct = |c: usize| {
+        let child = &tree[c];
+        let visits = child.visits.max(1) as f32;
+        child.wins / visits + EXPLORATION * (parent_visits / visits).sqrt()
+    };
+    tree[node]
+        .children
+        .iter()
+        .copied()
+        .max_by(|&a, &b| uct(a).partial_cmp(&uct(b)).unwrap_or(std::cmp::Ordering::Equal))
+        .expect("only called on expanded nodes")
+}
+
+fn backpropagate(tree: &mut [Node], leaf: usize, winner: Option<Player>) {
+    let mut node = Some(leaf);
+    while let Some(idx) = node {
+        let n = &mut tree[idx];
+        n.visits += 1;
+        n.wins += match winner {
+            Some(player) if player == n.mover => 1.,
+            Some(_) => 0.,
+            None => 0.5,
+        };
+        node = n.parent;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::ai::SimpleAI;
+    use crate::core::board::Board;
+    use crate::core::coord::HexCoord;
+    use crate::core::entities::Piece;
+    use crate::core::state::Phase;
+
+    #[test]
+    fn test_mcts_finds_winning_marker() {
+        // black is one run from winning, only the ring at (2, 0) completes it
+        let mut state = State::new(Board::new());
+        state.points_black = 2;
+        state.set_turn(Player::Black, Phase::PlaceMarker);
+        for (player, coord) in [
+            (Player::Black, (2, 0)),
+            (Player::Black, (-3, -3)),
+            (Player::White, (3, 3)),
+            (Player::White, (-3, 3)),
+        ] {
+            state.board
+                .place_unchecked(&Piece::Ring(player), &HexCoord::from(coord));
+        }
+        for i in -2..=1 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        state.compute_runs();
+
+        let before = state.clone();
+        let mut engines: Vec<Box<dyn Engine>> = vec![
+            Box::new(MctsAI::new_seeded(100, 1)),
+            Box::new(SimpleAI::new(Player::Black, 3)),
+        ];
+        for engine in engines.iter_mut() {
+            let action = engine.choose(&state).unwrap();
+            assert_eq!(action.coord(), HexCoord::new(2, 0));
+            assert!(state == before);
+        }
+    }
+
+    #[test]
+    fn test_mcts_seeded_and_legal() {
+        let state = State::from_notation("R e5\nR e7\nR f7\nR g7\n").unwrap();
+        let first = MctsAI::new_seeded(20, 3).choose(&state).unwrap();
+        let second = MctsAI::new_seeded(20, 3).choose(&state).unwrap();
+        assert_eq!(first, second);
+        assert!(first.is_legal(&state));
+    }
+}
@@ -5,4 +5,16 @@ pub mod entities;
 pub mod state;
 pub mod ai;
 pub mod command;
-pub mod coord;
\ No newline at end of file
+pub mod coord;
+pub mod notation;
+pub mod recorder;
+pub mod options;
+pub mod replay;
+pub mod packed;
+pub mod zobrist;
+pub mod transposition;
+pub mod prelude;
+pub mod opening_book;
+pub mod rng;
+pub mod mcts;
+pub mod tuning;
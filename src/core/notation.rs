@@ -0,0 +1,246 @@
+use std::fmt;
+
+use crate::core::coord::HexCoord;
+
+use super::actions::*;
+use super::state::State;
+
+// columns are lettered a..k from x = -5, rows numbered 1..11 from y = -5
+const LABEL_OFFSET: i8 = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    Empty,
+    // prefix other than R, M, X, x, D or none for a ring move
+    UnknownKind(String),
+    BadLabel(String),
+    // wrong number of fields for the kind of move
+    Malformed(String),
+    // well formed, but no legal move in the state
+    Illegal(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Empty => write!(f, "empty move"),
+            NotationError::UnknownKind(kind) => write!(f, "unknown move kind {:?}", kind),
+            NotationError::BadLabel(label) => write!(f, "bad field label {:?}", label),
+            NotationError::Malformed(s) => write!(f, "malformed move {:?}", s),
+            NotationError::Illegal(s) => write!(f, "{:?} is not a legal move", s),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+pub fn coord_to_label(coord: &HexCoord) -> String {
+    let col = (b'a' as i8 + coord.0 + LABEL_OFFSET) as u8 as char;
+    format!("{}{}", col, coord.1 + LABEL_OFFSET + 1)
+}
+
+pub fn label_to_coord(label: &str) -> Option<HexCoord> {
+    let mut chars = label.chars();
+    let col = chars.next()?;
+    if !col.is_ascii_lowercase() {
+        return None;
+    }
+    let row: i8 = chars.as_str().parse().ok()?;
+    Some(HexCoord::new(
+        col as i8 - b'a' as i8 - LABEL_OFFSET,
+        row.checked_sub(LABEL_OFFSET + 1)?,
+    ))
+}
+
+pub fn action_to_notation(action: &Action) -> String {
+    match action {
+        Action::PlaceRing(a) => format!("R {}", coord_to_label(&a.coord)),
+        Action::PlaceMarker(a) => format!("M {}", coord_to_label(&a.coord)),
+        Action::MoveRing(a) => format!("{}-{}", coord_to_label(&a.from), coord_to_label(&a.to)),
+        Action::RemoveRun(a) => format!(
+            "X {}-{}",
+            coord_to_label(&a.run[0]),
+            coord_to_label(&a.run[a.run.len() - 1])
+        ),
+        Action::RemoveRing(a) => format!("x {}", coord_to_label(&a.coord)),
+        Action::DeferMove(a) => format!("D {}", coord_to_label(&a.coord)),
+    }
+}
+
+// resolves a single notation entry against the legal moves of the given state
+pub fn notation_to_action(state: &State, s: &str) -> Result<Action, NotationError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(NotationError::Empty);
+    }
+    let (kind, rest) = match s.split_once(' ') {
+        Some((kind, rest)) => (kind, rest.trim()),
+        None => ("", s),
+    };
+    let n_coords = match kind {
+        "R" | "M" | "x" | "D" => 1,
+        "" | "X" => 2,
+        _ => return Err(NotationError::UnknownKind(kind.to_owned())),
+    };
+    let coords = rest
+        .split('-')
+        .map(|label| label_to_coord(label).ok_or_else(|| NotationError::BadLabel(label.to_owned())))
+        .collect::<Result<Vec<HexCoord>, NotationError>>()?;
+    if coords.len() != n_coords {
+        return Err(NotationError::Malformed(s.to_owned()));
+    }
+
+    let action = state.legal_moves().into_iter().find(|m| match (kind, m, coords.as_slice()) {
+        ("R", Action::PlaceRing(a), [c]) => a.coord == *c,
+        ("M", Action::PlaceMarker(a), [c]) => a.coord == *c,
+        ("", Action::MoveRing(a), [from, to]) => a.from == *from && a.to == *to,
+        ("X", Action::RemoveRun(a), [start, end]) => {
+            let (first, last) = (a.run[0], a.run[a.run.len() - 1]);
+            (first == *start && last == *end) || (first == *end && last == *start)
+        }
+        ("x", Action::RemoveRing(a), [c]) => a.coord == *c,
+        ("D", Action::DeferMove(a), [c]) => a.coord == *c,
+        _ => false,
+    });
+    action.ok_or_else(|| NotationError::Illegal(s.to_owned()))
+}
+
+pub fn history_to_notation(history: &[Action]) -> String {
+    history
+        .iter()
+        .map(|a| action_to_notation(a) + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::board::Board;
+    use crate::core::entities::{Piece, Player};
+    use crate::core::options::GameOptions;
+    use crate::core::state::Phase;
+
+    fn assert_roundtrip(state: &State, action: &Action) {
+        let notation = action_to_notation(action);
+        assert_eq!(notation_to_action(state, &notation).as_ref(), Ok(action), "{}", notation);
+    }
+
+    #[test]
+    fn test_label_roundtrip() {
+        let board = Board::new();
+        for c in board.board_coords() {
+            assert_eq!(label_to_coord(&coord_to_label(&c)), Some(c));
+        }
+        assert_eq!(coord_to_label(&HexCoord::new(0, 0)), "f6");
+        assert_eq!(label_to_coord("F6"), None);
+        assert_eq!(label_to_coord("f"), None);
+
+        // rim fields
+        assert_eq!(coord_to_label(&HexCoord::new(-4, -5)), "b1");
+        assert_eq!(coord_to_label(&HexCoord::new(5, 4)), "k10");
+        assert_eq!(label_to_coord("g11"), Some(HexCoord::new(1, 5)));
+        assert_eq!(label_to_coord("a2"), Some(HexCoord::new(-5, -4)));
+        for c in ["b1", "k10", "g11", "a2"] {
+            assert!(board.valid_coord(&label_to_coord(c).unwrap()), "{}", c);
+        }
+    }
+
+    #[test]
+    fn test_every_action_kind_roundtrips() {
+        let mut state = State::new(Board::new());
+        assert_roundtrip(&state, &state.legal_moves()[0]);
+
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 3));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-2, -3));
+        state.set_turn(Player::White, Phase::PlaceMarker);
+        assert_roundtrip(&state, &state.legal_moves()[0]);
+
+        let marker = state.legal_moves()[0].clone();
+        state.apply(&marker).unwrap();
+        for action in state.legal_moves() {
+            assert_roundtrip(&state, &action);
+        }
+
+        // the moving ring is off the board, put one back to give up after the run
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(1, 3));
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        state.set_turn(Player::White, Phase::RemoveRun);
+        let run = state.legal_moves()[0].clone();
+        assert!(matches!(run, Action::RemoveRun(_)));
+        assert_roundtrip(&state, &run);
+
+        state.apply(&run).unwrap();
+        let ring = state.legal_moves()[0].clone();
+        assert!(matches!(ring, Action::RemoveRing(_)));
+        assert_roundtrip(&state, &ring);
+
+        let options = GameOptions {
+            allow_marker_deferral: true,
+            ..Default::default()
+        };
+        let mut deferring = State::with_options(Board::new(), options);
+        deferring.board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(2, 3));
+        deferring.set_turn(Player::White, Phase::MoveRing(HexCoord::new(2, 3)));
+        let defer = deferring.legal_moves().pop().unwrap();
+        assert!(matches!(defer, Action::DeferMove(_)));
+        assert_roundtrip(&deferring, &defer);
+    }
+
+    #[test]
+    fn test_malformed_notation() {
+        let state = State::new(Board::new());
+        assert_eq!(notation_to_action(&state, "  "), Err(NotationError::Empty));
+        assert_eq!(
+            notation_to_action(&state, "Q f6"),
+            Err(NotationError::UnknownKind("Q".to_owned()))
+        );
+        assert_eq!(
+            notation_to_action(&state, "R zz"),
+            Err(NotationError::BadLabel("zz".to_owned()))
+        );
+        assert_eq!(
+            notation_to_action(&state, "R f6-f7"),
+            Err(NotationError::Malformed("R f6-f7".to_owned()))
+        );
+        assert_eq!(
+            notation_to_action(&state, "M f6"),
+            Err(NotationError::Illegal("M f6".to_owned()))
+        );
+        assert_eq!(
+            notation_to_action(&state, "R a1"),
+            Err(NotationError::Illegal("R a1".to_owned()))
+        );
+        // rows out of range
+        assert_eq!(label_to_coord("a-128"), None);
+        assert!(notation_to_action(&state, "R a-128").is_err());
+        assert_eq!(
+            notation_to_action(&state, "R a128"),
+            Err(NotationError::BadLabel("a128".to_owned()))
+        );
+        assert_eq!(
+            notation_to_action(&state, "R a127"),
+            Err(NotationError::Illegal("R a127".to_owned()))
+        );
+        assert!(State::from_notation("R a-128\n").is_none());
+    }
+
+    #[test]
+    fn test_notation_roundtrip() {
+        let mut state = State::new(Board::new());
+        for _ in 0..3 {
+            let action = state.legal_moves()[0].clone();
+            let notation = action_to_notation(&action);
+            let parsed = notation_to_action(&state, &notation).unwrap();
+            assert_eq!(action_to_notation(&parsed), notation);
+            state.apply(&parsed).unwrap();
+        }
+        assert!(notation_to_action(&state, "R zz").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use super::actions::Action;
+use super::board::{piece_index, Board};
+use super::notation::notation_to_action;
+use super::packed::{pack_coord, PackedMove};
+use super::state::{Phase, State};
+
+// one line per book position: the placements leading to it and the answer last, separated by
+// commas. lines starting with // are comments
+const STANDARD_BOOK: &str = "\
+// white opens off center
+R e5
+R e5, R e7
+R e5, R e7, R f7
+R e5, R e7, R f7, R g7
+R e5, R e7, R f7, R g7, R g5
+R e5, R e7, R f7, R g7, R g5, R f4
+R e5, R e7, R f7, R g7, R g5, R f4, R d8
+R e5, R e7, R f7, R g7, R g5, R f4, R d8, R d6
+R e5, R e7, R f7, R g7, R g5, R f4, R d8, R d6, R h6
+R e5, R e7, R f7, R g7, R g5, R f4, R d8, R d6, R h6, R h8
+// answers to a central first ring
+R f6, R e5
+R f6, R e5, R g7
+R f6, R e5, R g7, R g5
+";
+
+// the rings on the board, sorted so the order they were placed in does not matter
+type BookKey = Vec<(usize, u8)>;
+
+fn book_key(board: &Board) -> BookKey {
+    let mut key: BookKey = board
+        .pieces()
+        .map(|(c, p)| (piece_index(p), pack_coord(c)))
+        .collect();
+    key.sort_unstable();
+    key
+}
+
+// ring placements for the opening, keyed by the rings already placed
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    moves: HashMap<BookKey, PackedMove>,
+}
+
+impl OpeningBook {
+    pub fn standard() -> Self {
+        OpeningBook::parse(STANDARD_BOOK).expect("the standard opening book is legal")
+    }
+
+    // None if a line is not a legal sequence of ring placements
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut book = OpeningBook::default();
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with("//"));
+        for line in lines {
+            let mut state = State::new(Board::new());
+            let placements: Vec<&str> = line.split(',').collect();
+            let (answer, prefix) = placements.split_last()?;
+            for placement in prefix {
+                let action = notation_to_action(&state, placement).ok()?;
+                state.apply(&action).ok()?;
+            }
+            let answer = notation_to_action(&state, answer).ok()?;
+            if !matches!(answer, Action::PlaceRing(_)) {
+                return None;
+            }
+            book.moves
+                .insert(book_key(&state.board), PackedMove::from(&answer));
+        }
+        Some(book)
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    // the book placement for the position, only if it is legal there
+    pub fn lookup(&self, state: &State) -> Option<Action> {
+        if !state.at_phase(&Phase::PlaceRing) {
+            return None;
+        }
+        self.moves.get(&book_key(&state.board))?.to_action(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::command::Command;
+
+    #[test]
+    fn test_book_ignores_placement_order() {
+        let book = OpeningBook::standard();
+        assert_eq!(book.len(), 13);
+
+        let a = State::from_notation("R e5\nR e7\nR f7\nR g7\n").unwrap();
+        let b = State::from_notation("R f7\nR g7\nR e5\nR e7\n").unwrap();
+        let answer = book.lookup(&a).unwrap();
+        assert!(answer.is_legal(&a));
+        assert_eq!(book.lookup(&b), Some(answer));
+
+        let out_of_book = State::from_notation("R c3\n").unwrap();
+        assert_eq!(book.lookup(&out_of_book), None);
+        assert!(OpeningBook::parse("R e5, R e5").is_none());
+    }
+}
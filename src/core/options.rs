@@ -0,0 +1,175 @@
+use std::fmt;
+
+use super::board::Board;
+
+// rule variations, the default is the standard game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameOptions {
+    // practice mode: a placed marker may pass the turn without moving the ring
+    pub allow_marker_deferral: bool,
+    // markers in a row needed for a run
+    pub run_length: usize,
+    // removed rings needed to win, 1 for blitz
+    pub win_score: usize,
+    pub rings_per_player: usize,
+    // a player without a legal move loses, otherwise the game is drawn
+    pub blocked_player_loses: bool,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            allow_marker_deferral: false,
+            run_length: 5,
+            win_score: 3,
+            rings_per_player: 5,
+            blocked_player_loses: true,
+        }
+    }
+}
+
+impl GameOptions {
+    pub fn blitz() -> Self {
+        GameOptions {
+            win_score: 1,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    NoRings,
+    WinScoreOutOfRange { win_score: usize, rings_per_player: usize },
+    RunLengthOutOfRange { run_length: usize, max: usize },
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::NoRings => write!(f, "players need at least one ring"),
+            OptionsError::WinScoreOutOfRange {
+                win_score,
+                rings_per_player,
+            } => write!(
+                f,
+                "win score {} must be between 1 and {} rings per player",
+                win_score, rings_per_player
+            ),
+            OptionsError::RunLengthOutOfRange { run_length, max } => write!(
+                f,
+                "run length {} must be between 2 and the board span of {}",
+                run_length, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GameOptionsBuilder {
+    options: GameOptions,
+}
+
+impl GameOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow_marker_deferral(mut self, allow: bool) -> Self {
+        self.options.allow_marker_deferral = allow;
+        self
+    }
+
+    pub fn run_length(mut self, run_length: usize) -> Self {
+        self.options.run_length = run_length;
+        self
+    }
+
+    pub fn win_score(mut self, win_score: usize) -> Self {
+        self.options.win_score = win_score;
+        self
+    }
+
+    pub fn rings_per_player(mut self, rings: usize) -> Self {
+        self.options.rings_per_player = rings;
+        self
+    }
+
+    pub fn blocked_player_loses(mut self, loses: bool) -> Self {
+        self.options.blocked_player_loses = loses;
+        self
+    }
+
+    pub fn try_build(self) -> Result<GameOptions, OptionsError> {
+        let GameOptions {
+            run_length,
+            win_score,
+            rings_per_player,
+            ..
+        } = self.options;
+
+        if rings_per_player == 0 {
+            return Err(OptionsError::NoRings);
+        }
+        if win_score == 0 || win_score > rings_per_player {
+            return Err(OptionsError::WinScoreOutOfRange {
+                win_score,
+                rings_per_player,
+            });
+        }
+        let max = Board::new().max_line_length();
+        if !(2..=max).contains(&run_length) {
+            return Err(OptionsError::RunLengthOutOfRange { run_length, max });
+        }
+        Ok(self.options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tournament_options_build() {
+        let options = GameOptionsBuilder::new()
+            .rings_per_player(5)
+            .win_score(3)
+            .run_length(5)
+            .try_build()
+            .unwrap();
+        assert_eq!(options, GameOptions::default());
+        assert!(GameOptionsBuilder::new().win_score(1).try_build().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_options_rejected() {
+        assert_eq!(
+            GameOptionsBuilder::new().rings_per_player(0).try_build(),
+            Err(OptionsError::NoRings)
+        );
+        assert_eq!(
+            GameOptionsBuilder::new()
+                .rings_per_player(2)
+                .win_score(3)
+                .try_build(),
+            Err(OptionsError::WinScoreOutOfRange {
+                win_score: 3,
+                rings_per_player: 2
+            })
+        );
+        assert!(GameOptionsBuilder::new().win_score(0).try_build().is_err());
+
+        let max = Board::new().max_line_length();
+        assert_eq!(
+            GameOptionsBuilder::new().run_length(max + 1).try_build(),
+            Err(OptionsError::RunLengthOutOfRange {
+                run_length: max + 1,
+                max
+            })
+        );
+        assert!(GameOptionsBuilder::new().run_length(max).try_build().is_ok());
+        assert!(GameOptionsBuilder::new().run_length(1).try_build().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use super::actions::*;
+use super::coord::HexCoord;
+use super::state::State;
+
+// coords are packed into one byte each, x and y have to lie within -5..=5 as on the standard
+// board, Board::set_radius refuses anything larger
+const COORD_OFFSET: i8 = 5;
+const COORD_STRIDE: i8 = 11;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum MoveKind {
+    PlaceRing,
+    PlaceMarker,
+    MoveRing,
+    RemoveRun,
+    RemoveRing,
+    DeferMove,
+}
+
+// a three byte move for the transposition table and opening book, the player and run
+// cells are recovered from the state it is unpacked against
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PackedMove {
+    kind: MoveKind,
+    from: u8,
+    to: u8,
+}
+
+pub(crate) fn packable(coord: &HexCoord) -> bool {
+    let range = -COORD_OFFSET..=COORD_OFFSET;
+    range.contains(&coord.0) && range.contains(&coord.1)
+}
+
+// zobrist keys, the opening book and the history table rely on distinct packed coords
+pub(crate) fn pack_coord(coord: &HexCoord) -> u8 {
+    debug_assert!(packable(coord), "pack_coord: {:?} does not fit into a byte", coord);
+    ((coord.0 + COORD_OFFSET) * COORD_STRIDE + coord.1 + COORD_OFFSET) as u8
+}
+
+impl From<&Action> for PackedMove {
+    fn from(action: &Action) -> Self {
+        let (kind, from, to) = match action {
+            Action::PlaceRing(a) => (MoveKind::PlaceRing, a.coord, a.coord),
+            Action::PlaceMarker(a) => (MoveKind::PlaceMarker, a.coord, a.coord),
+            Action::MoveRing(a) => (MoveKind::MoveRing, a.from, a.to),
+            Action::RemoveRun(a) => (MoveKind::RemoveRun, a.run[0], a.run[a.run.len() - 1]),
+            Action::RemoveRing(a) => (MoveKind::RemoveRing, a.coord, a.coord),
+            Action::DeferMove(a) => (MoveKind::DeferMove, a.coord, a.coord),
+        };
+        PackedMove {
+            kind,
+            from: pack_coord(&from),
+            to: pack_coord(&to),
+        }
+    }
+}
+
+impl PackedMove {
+    // the matching legal move of state, None if the move does not apply there
+    pub fn to_action(&self, state: &State) -> Option<Action> {
+        state
+            .legal_moves()
+            .into_iter()
+            .find(|m| PackedMove::from(m) == *self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::board::Board;
+    use crate::core::command::Command;
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        assert!(std::mem::size_of::<PackedMove>() <= 4);
+
+        let mut state = State::new(Board::new());
+        for _ in 0..40 {
+            let moves = state.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            for m in moves.iter() {
+                assert_eq!(PackedMove::from(m).to_action(&state).as_ref(), Some(m));
+            }
+            moves[moves.len() / 2].execute(&mut state);
+        }
+
+        let packed = PackedMove::from(&Action::from(PlaceRing {
+            coord: HexCoord::new(0, 0),
+        }));
+        assert_eq!(packed.to_action(&state), None);
+    }
+}
@@ -0,0 +1,29 @@
+// the common engine types in one import: use crate::core::prelude::*;
+pub use super::actions::Action;
+pub use super::ai::{Difficulty, Engine, Evaluator, SimpleAI};
+pub use super::board::Board;
+pub use super::command::Command;
+pub use super::coord::HexCoord;
+pub use super::entities::{Piece, Player};
+pub use super::game::GameResult;
+pub use super::options::GameOptions;
+pub use super::state::{Phase, State};
+
+#[cfg(test)]
+mod test {
+    use crate::core::prelude::*;
+
+    #[test]
+    fn test_play_with_prelude_only() {
+        let mut state = State::new(Board::new());
+        let action: Action = state.legal_moves()[0].clone();
+        action.execute(&mut state);
+
+        assert_eq!(state.board.occupied(&action.coord()), Some(&Piece::Ring(Player::White)));
+        assert_eq!(state.phase(), Phase::PlaceRing);
+        assert_eq!(state.player(), Player::Black);
+        assert!(state.won_by().map(GameResult::Won).is_none());
+        assert_eq!(state.options, GameOptions::default());
+        assert!(HexCoord::new(0, 0).is_on_board(Board::new().get_radius()));
+    }
+}
@@ -0,0 +1,102 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::actions::Action;
+use super::notation::action_to_notation;
+
+// appends every executed action to a notation file, no-op on wasm
+pub struct MoveRecorder {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    recorded: usize,
+}
+
+impl MoveRecorder {
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut recorder = MoveRecorder {
+            path: path.as_ref().to_path_buf(),
+            writer: None,
+            recorded: 0,
+        };
+        if !cfg!(target_arch = "wasm32") {
+            recorder.writer = Some(BufWriter::new(File::create(&recorder.path)?));
+        }
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, history: &[Action]) -> io::Result<()> {
+        if self.writer.is_none() || history.len() == self.recorded {
+            return Ok(());
+        }
+        // after an undo or restart the log no longer matches, start over
+        if history.len() < self.recorded {
+            self.writer = Some(BufWriter::new(
+                OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .open(&self.path)?,
+            ));
+            self.recorded = 0;
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            for action in &history[self.recorded..] {
+                writeln!(writer, "{}", action_to_notation(action))?;
+            }
+        }
+        self.recorded = history.len();
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MoveRecorder {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::board::Board;
+    use crate::core::state::State;
+
+    #[test]
+    fn test_recorded_game_reimports() {
+        let path = std::env::temp_dir().join("yinsh_recorder_test.txt");
+        let mut recorder = MoveRecorder::new(&path).unwrap();
+        let mut state = State::new(Board::new());
+
+        for _ in 0..3 {
+            let action = state.legal_moves()[0].clone();
+            state.apply(&action).unwrap();
+            recorder.record(&state.history).unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let notation = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(notation.lines().count(), 3);
+        let imported = State::from_notation(&notation).unwrap();
+        assert_eq!(imported.to_board_array(), state.to_board_array());
+
+        // undoing rewrites the log
+        state.undo();
+        recorder.record(&state.history).unwrap();
+        recorder.flush().unwrap();
+        let notation = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(notation.lines().count(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use std::io;
+use std::path::Path;
+
+use super::actions::Action;
+use super::board::Board;
+use super::command::Command;
+use super::options::GameOptions;
+use super::state::State;
+
+// a finished or ongoing game as the sequence of its actions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    actions: Vec<Action>,
+    options: GameOptions,
+}
+
+impl Replay {
+    pub fn from_state(state: &State) -> Self {
+        Replay {
+            actions: state.history.clone(),
+            options: state.options,
+        }
+    }
+
+    pub fn from_notation(notation: &str) -> Option<Self> {
+        State::from_notation(notation).map(|state| Replay::from_state(&state))
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    // the state after each action, in order
+    pub fn states(&self) -> impl Iterator<Item = State> + '_ {
+        let mut state = State::with_options(Board::new(), self.options);
+        self.actions.iter().map(move |action| {
+            action.execute(&mut state);
+            state.clone()
+        })
+    }
+
+    // one standalone svg per action, independent of the macroquad frontend
+    pub fn svg_frames(&self, radius: f32) -> Vec<String> {
+        self.states().map(|state| state.board.to_svg(radius)).collect()
+    }
+
+    // writes frame_000.svg, frame_001.svg, ... to be assembled into a gif externally
+    pub fn write_svg_frames<P: AsRef<Path>>(&self, dir: P, radius: f32) -> io::Result<usize> {
+        let frames = self.svg_frames(radius);
+        for (idx, frame) in frames.iter().enumerate() {
+            std::fs::write(dir.as_ref().join(format!("frame_{:03}.svg", idx)), frame)?;
+        }
+        Ok(frames.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_three_moves_three_frames() {
+        let replay = Replay::from_notation("R f6\nR f7\nR g6\n").unwrap();
+        assert_eq!(replay.len(), 3);
+
+        let frames = replay.svg_frames(100.);
+        assert_eq!(frames.len(), 3);
+        assert_ne!(frames[0], frames[1]);
+        assert_ne!(frames[1], frames[2]);
+        assert_ne!(frames[0], frames[2]);
+    }
+}
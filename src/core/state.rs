@@ -1,11 +1,18 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use itertools::Itertools;
+
 use crate::core::coord::*;
 use crate::core::board::*;
 use crate::core::entities::*;
+use crate::core::options::*;
 
 use super::actions::*;
 use super::command::*;
+use super::notation::notation_to_action;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum Phase {
     PlaceRing,
     PlaceMarker,
@@ -13,6 +20,7 @@ pub enum Phase {
     RemoveRun,
     RemoveRing,
     PlayerWon(Player),
+    Draw,
 }
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum StateChange {
@@ -23,8 +31,33 @@ pub enum StateChange {
     MarkerRemoved(Player, HexCoord),
     RingRemoved(Player, HexCoord),
     PlayerScored(Player),
+    // a scored ring was taken back by an undo
+    ScoreReverted(Player),
+    PhaseChanged(Phase),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IllegalMoveError {
+    WrongPhase(Phase),
+    NotAllowed(HexCoord),
+}
+
+impl fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalMoveError::WrongPhase(phase) => {
+                write!(f, "move not allowed in phase {:?}", phase)
+            }
+            IllegalMoveError::NotAllowed(coord) => write!(f, "illegal move at {:?}", coord),
+        }
+    }
 }
 
+impl std::error::Error for IllegalMoveError {}
+
+// side to move and phase, followed by one entry per board cell
+pub const BOARD_ARRAY_HEADER_LEN: usize = 2;
+
 #[derive(Clone)]
 pub struct State {
     pub board: Board,
@@ -36,13 +69,41 @@ pub struct State {
     pub runs_white: Vec<Vec<HexCoord>>,
     pub runs_black: Vec<Vec<HexCoord>>,
     pub history: Vec<Action>,
+    // undone actions, the most recent last, cleared by any fresh action
+    pub redo_stack: Vec<Action>,
     pub last_state_change: Vec<StateChange>,
+    pub draw_offered_by: Option<Player>,
+    pub resigned_by: Option<Player>,
+    pub options: GameOptions,
+}
+
+// last_state_change only describes the latest action and is left out, runs are
+// compared regardless of the order the board happened to yield them in
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        let sorted = |runs: &Vec<Vec<HexCoord>>| runs.iter().cloned().sorted().collect::<Vec<_>>();
+        self.board == other.board
+            && self.current_player == other.current_player
+            && self.current_phase == other.current_phase
+            && self.points_white == other.points_white
+            && self.points_black == other.points_black
+            && sorted(&self.runs_white) == sorted(&other.runs_white)
+            && sorted(&self.runs_black) == sorted(&other.runs_black)
+            && self.history == other.history
+            && self.draw_offered_by == other.draw_offered_by
+            && self.resigned_by == other.resigned_by
+            && self.options == other.options
+    }
 }
 
 impl State {
     pub fn new(board: Board) -> Self {
+        State::with_options(board, GameOptions::default())
+    }
+
+    pub fn with_options(board: Board, options: GameOptions) -> Self {
         State {
-            board,
+            board: board.with_run_length(options.run_length),
             current_player: Player::White,
             current_phase: Phase::PlaceRing,
             points_white: 0,
@@ -50,12 +111,18 @@ impl State {
             runs_white: vec![],
             runs_black: vec![],
             history: vec![],
+            redo_stack: vec![],
             last_state_change: vec![],
+            draw_offered_by: None,
+            resigned_by: None,
+            options,
         }
     }
 
     pub fn new_action(&mut self) {
         self.last_state_change.clear();
+        // a pending draw offer lapses as soon as the game moves on
+        self.draw_offered_by = None;
     }
 
     fn push_state_change(&mut self, state_change: StateChange) {
@@ -96,14 +163,15 @@ impl State {
     }
 
     pub fn remove_ring(&mut self, player: &Player, coord: &HexCoord) {
-        self.board.remove(coord);
+        let removed = self.board.remove(coord);
+        assert_removed(removed, Piece::Ring(*player), coord);
         self.push_state_change(StateChange::RingRemoved(*player, *coord));
     }
 
     pub fn place_marker(&mut self, player: &Player, coord: &HexCoord) {
         let piece = Piece::Marker(*player);
         let removed = self.board.place_unchecked(&piece, coord);
-        self.push_state_change(StateChange::RingPlaced(*player, *coord));
+        self.push_state_change(StateChange::MarkerPlaced(*player, *coord));
         if let Some(piece) = removed {
             if piece.is_marker() {
                 self.push_state_change(StateChange::MarkerRemoved(piece.owner(), *coord));
@@ -115,7 +183,8 @@ impl State {
     }
 
     pub fn remove_marker(&mut self, player: &Player, coord: &HexCoord) {
-        self.board.remove(coord);
+        let removed = self.board.remove(coord);
+        assert_removed(removed, Piece::Marker(*player), coord);
         self.push_state_change(StateChange::MarkerRemoved(*player, *coord));
     }
 
@@ -125,6 +194,45 @@ impl State {
             .extend(flipped.into_iter().map(StateChange::MarkerFlipped));
     }
 
+    // the ring has to move on after the marker is placed, unless deferring is allowed
+    pub fn can_place_marker(&self, ring: &HexCoord) -> bool {
+        self.options.allow_marker_deferral || self.board.ring_mobility(ring) > 0
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.won_by().is_some() || self.is_draw()
+    }
+
+    // the game is not over but the player to move has nothing to play
+    pub fn is_blocked(&self) -> bool {
+        match self.current_phase {
+            // checked at the start of every turn, stops at the first usable ring. a player
+            // without any ring would have won long before, such positions are only set up
+            Phase::PlaceMarker => {
+                let mut rings = self.board.player_rings(self.current_player).peekable();
+                rings.peek().is_some() && !rings.any(|c| self.can_place_marker(c))
+            }
+            _ => !self.is_terminal() && self.legal_moves().is_empty(),
+        }
+    }
+
+    // called when a turn starts, a blocked player loses or draws as the options say
+    pub fn end_if_blocked(&mut self) {
+        if !self.is_blocked() {
+            return;
+        }
+        if self.options.blocked_player_loses {
+            self.set_phase(Phase::PlayerWon(self.current_player.other()));
+        } else {
+            self.set_phase(Phase::Draw);
+        }
+    }
+
+    // exactly one legal move, e.g. the last ring to remove or a lone run
+    pub fn is_forced(&self) -> bool {
+        self.legal_moves().len() == 1
+    }
+
     pub fn legal_moves(&self) -> Vec<Action> {
         match self.current_phase {
             Phase::PlaceRing => self
@@ -137,20 +245,31 @@ impl State {
             Phase::PlaceMarker => self
                 .board
                 .player_rings(self.current_player)
+                .filter(|c| self.can_place_marker(c))
+                .sorted()
                 .map(|c| Action::from(PlaceMarker { coord: *c }))
                 .collect::<Vec<Action>>(),
-            Phase::MoveRing(from) => self
-                .board
-                .ring_targets(&from)
-                .iter()
-                .map(|c| {
-                    Action::from(MoveRing {
-                        player: self.current_player,
-                        from,
-                        to: *c,
+            Phase::MoveRing(from) => {
+                let mut moves: Vec<Action> = self
+                    .board
+                    .ring_targets(&from)
+                    .iter()
+                    .map(|c| {
+                        Action::from(MoveRing {
+                            player: self.current_player,
+                            from,
+                            to: *c,
+                        })
                     })
-                })
-                .collect(),
+                    .collect();
+                if self.options.allow_marker_deferral {
+                    moves.push(Action::from(DeferMove {
+                        coord: from,
+                        player: self.current_player,
+                    }));
+                }
+                moves
+            }
             // TODO: this does not always work for multiple simultaneous runs!!
             Phase::RemoveRun => self
                 .current_player_runs()
@@ -167,6 +286,7 @@ impl State {
             Phase::RemoveRing => self
                 .board
                 .player_rings(self.current_player)
+                .sorted()
                 .map(|c| {
                     Action::from(RemoveRing {
                         player: self.current_player,
@@ -174,35 +294,212 @@ impl State {
                     })
                 })
                 .collect::<Vec<Action>>(),
-            Phase::PlayerWon(_) => Vec::new(),
+            Phase::PlayerWon(_) | Phase::Draw => Vec::new(),
+        }
+    }
+
+    // run removals ordered by the number of the player's runs they leave intact, overlapping
+    // runs are destroyed together with the removed one
+    pub fn best_run_choices(&self) -> Vec<Action> {
+        let mut choices: Vec<(usize, Action)> = self
+            .legal_moves()
+            .into_iter()
+            .filter_map(|action| match &action {
+                Action::RemoveRun(a) => {
+                    let remaining = self.current_player_runs_excluding(&a.run).len();
+                    Some((remaining, action))
+                }
+                _ => None,
+            })
+            .collect();
+        choices.sort_by_key(|(remaining, _)| std::cmp::Reverse(*remaining));
+        choices.into_iter().map(|(_, action)| action).collect()
+    }
+
+    // the ring worth least when removing one after scoring, the one with the fewest moves
+    pub fn suggested_ring_removal(&self) -> Option<HexCoord> {
+        if self.current_phase != Phase::RemoveRing {
+            return None;
+        }
+        self.board
+            .player_rings(self.current_player)
+            .sorted()
+            .min_by_key(|c| self.board.ring_mobility(c))
+            .copied()
+    }
+
+    // replays a game recorded one action per line, see core::notation
+    pub fn from_notation(notation: &str) -> Option<State> {
+        let mut state = State::new(Board::new());
+        for line in notation.lines().filter(|l| !l.trim().is_empty()) {
+            let action = notation_to_action(&state, line).ok()?;
+            state.apply(&action).ok()?;
+        }
+        Some(state)
+    }
+
+    // legal moves keyed by the ring they move from, or the first cell of their run
+    pub fn legal_moves_grouped(&self) -> BTreeMap<HexCoord, Vec<Action>> {
+        let mut grouped: BTreeMap<HexCoord, Vec<Action>> = BTreeMap::new();
+        for action in self.legal_moves() {
+            let key = match &action {
+                Action::MoveRing(m) => m.from,
+                _ => action.coord(),
+            };
+            grouped.entry(key).or_default().push(action);
+        }
+        grouped
+    }
+
+    pub fn apply(&mut self, action: &Action) -> Result<(), IllegalMoveError> {
+        if !action.is_legal(self) {
+            return Err(IllegalMoveError::NotAllowed(action.coord()));
+        }
+        action.execute(self);
+        Ok(())
+    }
+
+    // removes the run and the ring in one step, leaving the state untouched on failure
+    pub fn resolve_run(
+        &mut self,
+        run: &[HexCoord],
+        ring: &HexCoord,
+    ) -> Result<(), IllegalMoveError> {
+        if self.current_phase != Phase::RemoveRun {
+            return Err(IllegalMoveError::WrongPhase(self.current_phase));
+        }
+        let run_idx = self
+            .current_player_runs()
+            .iter()
+            .position(|r| r == run)
+            .ok_or_else(|| IllegalMoveError::NotAllowed(run.first().copied().unwrap_or(*ring)))?;
+        let redo_stack = self.redo_stack.clone();
+
+        self.apply(&Action::from(RemoveRun {
+            run_idx,
+            run: run.to_vec(),
+            coord: run[0],
+        }))?;
+
+        let remove_ring = Action::from(RemoveRing {
+            player: self.current_player,
+            coord: *ring,
+        });
+        if let Err(err) = self.apply(&remove_ring) {
+            self.undo();
+            self.redo_stack = redo_stack;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    // applies all actions or none, the error carries the index of the first illegal one
+    pub fn apply_all(&mut self, actions: &[Action]) -> Result<(), (usize, IllegalMoveError)> {
+        let redo_stack = self.redo_stack.clone();
+        for (idx, action) in actions.iter().enumerate() {
+            if let Err(err) = self.apply(action) {
+                for _ in 0..idx {
+                    self.undo();
+                }
+                self.redo_stack = redo_stack;
+                return Err((idx, err));
+            }
         }
+        Ok(())
     }
 
     pub fn undo(&mut self) -> bool {
         if let Some(m) = self.history.pop() {
             m.undo(self);
+            self.redo_stack.push(m);
             return true;
         }
         false
     }
 
+    // executes the last undone action again, false if there is none
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(m) => {
+                // executing clears the stack, the older undone actions stay redoable
+                let older = std::mem::take(&mut self.redo_stack);
+                m.execute(self);
+                self.redo_stack = older;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // records an executed action, see Command::execute
+    pub fn push_history(&mut self, action: Action) {
+        self.history.push(action);
+        self.redo_stack.clear();
+    }
+
     pub fn last_state_change(&self) -> Vec<StateChange> {
         self.last_state_change.clone()
     }
 
-    pub fn current_player_runs(&self) -> &Vec<Vec<HexCoord>> {
-        match self.current_player {
+    pub fn runs_for(&self, player: &Player) -> &Vec<Vec<HexCoord>> {
+        match player {
             Player::Black => &self.runs_black,
             Player::White => &self.runs_white,
         }
     }
 
+    pub fn current_player_runs(&self) -> &Vec<Vec<HexCoord>> {
+        self.runs_for(&self.current_player)
+    }
+
+    pub fn current_player_runs_excluding(&self, excluded: &[HexCoord]) -> Vec<Vec<HexCoord>> {
+        self.current_player_runs()
+            .iter()
+            .filter(|run| !run.iter().any(|c| excluded.contains(c)))
+            .cloned()
+            .collect()
+    }
+
+    // player of the last ring move, None before the first one
+    pub fn last_mover(&self) -> Option<Player> {
+        self.history.iter().rev().find_map(|action| match action {
+            Action::MoveRing(m) => Some(m.player),
+            Action::DeferMove(m) => Some(m.player),
+            _ => None,
+        })
+    }
+
     pub fn next_player(&mut self) {
         self.current_player = self.current_player.other();
     }
 
+    pub fn set_turn(&mut self, player: Player, phase: Phase) {
+        self.current_player = player;
+        self.current_phase = phase;
+        self.compute_runs();
+    }
+
     pub fn set_phase(&mut self, phase: Phase) {
         self.current_phase = phase;
+        self.push_state_change(StateChange::PhaseChanged(phase));
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.current_phase
+    }
+
+    pub fn player(&self) -> Player {
+        self.current_player
+    }
+
+    // removed rings a player needs to win, see GameOptions
+    pub fn win_score(&self) -> usize {
+        self.options.win_score
+    }
+
+    // (white, black)
+    pub fn scores(&self) -> (usize, usize) {
+        (self.points_white, self.points_black)
     }
 
     pub fn at_phase(&self, phase: &Phase) -> bool {
@@ -248,6 +545,7 @@ impl State {
             Player::White => self.points_white -= 1,
             Player::Black => self.points_black -= 1,
         }
+        self.push_state_change(StateChange::ScoreReverted(*player));
     }
 
     pub fn get_score(&self, player: &Player) -> usize {
@@ -264,6 +562,106 @@ impl State {
         None
     }
 
+    pub fn validate(&self) {
+        for (player, runs) in [
+            (Player::White, &self.runs_white),
+            (Player::Black, &self.runs_black),
+        ] {
+            self.board.assert_runs_consistent(&player);
+            for run in runs {
+                self.board.assert_run_consistent(&player, run);
+            }
+        }
+    }
+
+    pub fn to_board_array(&self) -> Vec<i8> {
+        let mut res = Vec::with_capacity(BOARD_ARRAY_HEADER_LEN + self.board.board_coords().len());
+        res.push(player_code(&self.current_player));
+        res.push(phase_code(&self.current_phase));
+        res.extend(
+            self.board
+                .board_coords()
+                .iter()
+                .map(|c| piece_code(self.board.occupied(c))),
+        );
+        res
+    }
+
+    pub fn board_from_array(array: &[i8]) -> Option<Board> {
+        let mut board = Board::new();
+        let coords = board.board_coords();
+        if array.len() != BOARD_ARRAY_HEADER_LEN + coords.len() {
+            return None;
+        }
+        for (c, code) in coords.iter().zip(&array[BOARD_ARRAY_HEADER_LEN..]) {
+            if let Some(piece) = piece_from_code(*code)? {
+                board.place_unchecked(&piece, c);
+            }
+        }
+        Some(board)
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.current_phase == Phase::Draw
+    }
+
+    pub fn offer_draw(&mut self, player: &Player) {
+        if self.won_by().is_none() && !self.is_draw() {
+            self.draw_offered_by = Some(*player);
+        }
+    }
+
+    pub fn accept_draw(&mut self, player: &Player) -> bool {
+        if self.draw_offered_by == Some(player.other()) {
+            self.draw_offered_by = None;
+            self.set_phase(Phase::Draw);
+            return true;
+        }
+        false
+    }
+
+    pub fn decline_draw(&mut self) {
+        self.draw_offered_by = None;
+    }
+
+    // the opponent wins on the spot
+    pub fn resign(&mut self, player: &Player) {
+        if self.won_by().is_none() && !self.is_draw() {
+            self.resigned_by = Some(*player);
+            self.set_phase(Phase::PlayerWon(player.other()));
+        }
+    }
+
+    pub fn pretty_print(&self) -> String {
+        let mut res = format!("{:?} to move, {:?}\n", self.current_player, self.current_phase);
+        let radius = self.board.get_radius().ceil() as i8;
+        for y in (-radius..=radius).rev() {
+            let line: String = (-radius..=radius)
+                .map(|x| {
+                    let c = HexCoord::new(x, y);
+                    match self.board.occupied(&c) {
+                        _ if !self.board.valid_coord(&c) => ' ',
+                        Some(Piece::Ring(Player::White)) => 'W',
+                        Some(Piece::Ring(Player::Black)) => 'B',
+                        Some(Piece::Marker(Player::White)) => 'w',
+                        Some(Piece::Marker(Player::Black)) => 'b',
+                        None => '.',
+                    }
+                })
+                .collect();
+            res += line.trim_end();
+            res += "\n";
+        }
+        res += &format!(
+            "{}\npoints: {} - {}\n",
+            self.board.summary_line(),
+            self.points_white,
+            self.points_black
+        );
+        res
+    }
+
+    // options are kept, clearing the board keeps its run length
     pub fn restart(&mut self) {
         self.board.clear();
         self.current_phase = Phase::PlaceRing;
@@ -273,6 +671,663 @@ impl State {
         self.runs_white.clear();
         self.runs_black.clear();
         self.history.clear();
+        self.redo_stack.clear();
         self.last_state_change.clear();
+        self.draw_offered_by = None;
+        self.resigned_by = None;
+    }
+}
+
+// a mismatch means the state got out of sync with the board, e.g. during undo
+fn assert_removed(removed: Option<Piece>, expected: Piece, coord: &HexCoord) {
+    debug_assert!(
+        removed == Some(expected),
+        "removed {:?} at {:?}, expected {:?}",
+        removed,
+        coord,
+        expected
+    );
+}
+
+fn player_code(player: &Player) -> i8 {
+    match player {
+        Player::White => 0,
+        Player::Black => 1,
+    }
+}
+
+fn phase_code(phase: &Phase) -> i8 {
+    match phase {
+        Phase::PlaceRing => 0,
+        Phase::PlaceMarker => 1,
+        Phase::MoveRing(_) => 2,
+        Phase::RemoveRun => 3,
+        Phase::RemoveRing => 4,
+        Phase::PlayerWon(_) => 5,
+        Phase::Draw => 6,
+    }
+}
+
+fn piece_code(piece: Option<&Piece>) -> i8 {
+    match piece {
+        None => 0,
+        Some(Piece::Marker(Player::White)) => 1,
+        Some(Piece::Marker(Player::Black)) => 2,
+        Some(Piece::Ring(Player::White)) => 3,
+        Some(Piece::Ring(Player::Black)) => 4,
+    }
+}
+
+// outer None for invalid codes, inner None for empty cells
+fn piece_from_code(code: i8) -> Option<Option<Piece>> {
+    match code {
+        0 => Some(None),
+        1 => Some(Some(Piece::Marker(Player::White))),
+        2 => Some(Some(Piece::Marker(Player::Black))),
+        3 => Some(Some(Piece::Ring(Player::White))),
+        4 => Some(Some(Piece::Ring(Player::Black))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_board_array_roundtrip() {
+        let mut state = State::new(Board::new());
+        state.board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 4));
+        state.board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-1, -2));
+        state.board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(1, 0));
+        state.current_player = Player::Black;
+        state.set_phase(Phase::PlaceMarker);
+
+        let array = state.to_board_array();
+        assert_eq!(
+            array.len(),
+            BOARD_ARRAY_HEADER_LEN + state.board.board_coords().len()
+        );
+        assert_eq!(array[0], 1);
+        assert_eq!(array[1], 1);
+
+        let board = State::board_from_array(&array).unwrap();
+        for c in state.board.board_coords() {
+            assert_eq!(board.occupied(&c), state.board.occupied(&c));
+        }
+        assert_eq!(board.rings().count(), 2);
+        assert_eq!(board.markers().count(), 2);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        state.compute_runs();
+        state.validate();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "expected Ring(White)")]
+    fn test_remove_ring_on_marker() {
+        let mut state = State::new(Board::new());
+        let c = HexCoord::new(1, 2);
+        state.place_marker(&Player::White, &c);
+        state.remove_ring(&Player::White, &c);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent run")]
+    fn test_validate_corrupted_run() {
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        state.compute_runs();
+        state.runs_white[0][4] = HexCoord::new(2, 1);
+        state.board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(2, 1));
+        state.validate();
+    }
+
+    #[test]
+    fn test_set_turn_recomputes_runs() {
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        assert!(!state.has_run(&Player::Black));
+
+        state.set_turn(Player::Black, Phase::RemoveRun);
+        assert_eq!(state.current_player, Player::Black);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+        assert!(state.has_run(&Player::Black));
+        assert!(!state.has_run(&Player::White));
+        assert_eq!(state.current_player_runs(), &state.board.runs(&Player::Black));
+
+        state.board.flip_marker(&HexCoord::new(0, 0));
+        state.set_turn(Player::White, Phase::PlaceMarker);
+        assert!(!state.has_run(&Player::Black));
+    }
+
+    fn state_with_white_run() -> (State, Vec<HexCoord>, HexCoord) {
+        let mut state = State::new(Board::new());
+        let run: Vec<HexCoord> = (-2..=2).map(|i| HexCoord::new(i, 0)).collect();
+        for c in run.iter() {
+            state.board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        let ring = HexCoord::new(2, 3);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &ring);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-2, -3));
+        state.set_turn(Player::White, Phase::RemoveRun);
+        (state, run, ring)
+    }
+
+    #[test]
+    fn test_resolve_run() {
+        let (mut state, run, ring) = state_with_white_run();
+
+        assert!(state.resolve_run(&run, &ring).is_ok());
+        assert_eq!(state.get_score(&Player::White), 1);
+        assert_eq!(state.current_player, Player::Black);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.board.markers().count(), 0);
+        assert!(!state.board.player_ring_at(&ring, &Player::White));
+        assert_eq!(state.history.len(), 2);
+    }
+
+    #[test]
+    fn test_best_run_choice_keeps_crossing_run() {
+        let mut state = State::new(Board::new());
+        let row: Vec<HexCoord> = (-2..=3).map(|x| HexCoord::new(x, 0)).collect();
+        let column: Vec<HexCoord> = (0..=4).map(|y| HexCoord::new(3, y)).collect();
+        for c in row.iter().chain(column.iter()) {
+            assert!(state.board.valid_coord(c));
+            state.board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        state.set_turn(Player::White, Phase::RemoveRun);
+        assert_eq!(state.legal_moves().len(), 3);
+
+        let choices = state.best_run_choices();
+        assert_eq!(choices.len(), 3);
+        let run_of = |action: &Action| match action {
+            Action::RemoveRun(a) => a.run.clone(),
+            _ => panic!("expected a run removal, got {:?}", action),
+        };
+        // taking the row end shared with the column destroys both runs
+        assert!(run_of(&choices[2]).contains(&HexCoord::new(3, 0)));
+        assert!(!run_of(&choices[2]).contains(&HexCoord::new(3, 1)));
+        let best = run_of(&choices[0]);
+        assert_eq!(state.current_player_runs_excluding(&best).len(), 1);
+    }
+
+    #[test]
+    fn test_suggested_ring_removal_least_mobile() {
+        let mut state = State::new(Board::new());
+        let rings = [HexCoord::new(0, 0), HexCoord::new(4, 4), HexCoord::new(-2, 1)];
+        for c in &rings {
+            state.board.place_unchecked(&Piece::Ring(Player::White), c);
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(3, 0));
+        assert_eq!(state.suggested_ring_removal(), None);
+
+        state.set_turn(Player::White, Phase::RemoveRing);
+        let least = rings
+            .iter()
+            .min_by_key(|c| state.board.ring_mobility(c))
+            .unwrap();
+        assert_eq!(*least, HexCoord::new(4, 4));
+        assert_eq!(state.suggested_ring_removal(), Some(*least));
+    }
+
+    #[test]
+    fn test_is_forced() {
+        let mut state = State::new(Board::new());
+        assert!(!state.is_forced());
+
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(1, 2));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-1, 2));
+        state.set_turn(Player::White, Phase::RemoveRing);
+        assert!(state.is_forced());
+    }
+
+    #[test]
+    fn test_accessors_after_scoring() {
+        let (mut state, run, ring) = state_with_white_run();
+        assert_eq!(state.phase(), Phase::RemoveRun);
+        assert_eq!(state.player(), Player::White);
+        assert_eq!(state.scores(), (0, 0));
+
+        state.resolve_run(&run, &ring).unwrap();
+        assert_eq!(state.phase(), Phase::PlaceMarker);
+        assert_eq!(state.player(), Player::Black);
+        assert_eq!(state.scores(), (1, 0));
+    }
+
+    #[test]
+    fn test_undo_reverts_score_with_change() {
+        let (mut state, run, ring) = state_with_white_run();
+        state.resolve_run(&run, &ring).unwrap();
+        assert!(state
+            .last_state_change()
+            .contains(&StateChange::PlayerScored(Player::White)));
+
+        assert!(state.undo());
+        assert_eq!(state.scores(), (0, 0));
+        let changes = state.last_state_change();
+        assert!(changes.contains(&StateChange::ScoreReverted(Player::White)));
+        assert!(changes.contains(&StateChange::RingPlaced(Player::White, ring)));
+    }
+
+    fn apply_state_change(board: &mut Board, change: &StateChange) {
+        match change {
+            StateChange::RingPlaced(player, c) => {
+                board.place_unchecked(&Piece::Ring(*player), c);
+            }
+            StateChange::MarkerPlaced(player, c) => {
+                board.place_unchecked(&Piece::Marker(*player), c);
+            }
+            StateChange::RingMoved(player, from, to) => {
+                if board.player_ring_at(from, player) {
+                    board.remove(from);
+                }
+                board.place_unchecked(&Piece::Ring(*player), to);
+            }
+            StateChange::MarkerFlipped(c) => {
+                board.flip_marker(c);
+            }
+            // a removal following a placement on the same field refers to the replaced piece
+            StateChange::MarkerRemoved(_, c) => {
+                if board.marker_at(c).is_some() {
+                    board.remove(c);
+                }
+            }
+            StateChange::RingRemoved(_, c) => {
+                if board.ring_at(c).is_some() {
+                    board.remove(c);
+                }
+            }
+            StateChange::PlayerScored(_)
+            | StateChange::ScoreReverted(_)
+            | StateChange::PhaseChanged(_) => (),
+        }
+    }
+
+    // the positions of one self-play game with marker deferral, played once and shared by the
+    // tests that walk through a whole game
+    fn fixed_game() -> &'static [State] {
+        use crate::core::ai::{self_play, SimpleAI};
+
+        static GAME: std::sync::OnceLock<Vec<State>> = std::sync::OnceLock::new();
+        GAME.get_or_init(|| {
+            let options = GameOptions {
+                allow_marker_deferral: true,
+                ..Default::default()
+            };
+            let players = [
+                SimpleAI::new(Player::White, 0),
+                SimpleAI::new(Player::Black, 0),
+            ];
+            self_play(State::with_options(Board::new(), options), players, 300)
+        })
+    }
+
+    // rebuilds the board by replaying history and from the recorded state changes
+    fn assert_state_sources_agree(state: &State) {
+        let mut replayed = State::with_options(Board::new(), state.options);
+        let mut from_changes = Board::new();
+        for action in state.history.iter() {
+            replayed.apply(action).unwrap();
+            replayed
+                .last_state_change()
+                .iter()
+                .for_each(|change| apply_state_change(&mut from_changes, change));
+        }
+        assert!(replayed.board == state.board, "replayed history diverged");
+        assert!(
+            from_changes == state.board,
+            "state changes diverged:\n{}\n{}",
+            state.pretty_print(),
+            State { board: from_changes.clone(), ..state.clone() }.pretty_print()
+        );
+    }
+
+    #[test]
+    fn test_state_sources_agree_after_place_marker() {
+        let mut state = State::new(Board::new());
+        while !matches!(state.current_phase, Phase::MoveRing(_)) {
+            let action = state.legal_moves()[0].clone();
+            state.apply(&action).unwrap();
+        }
+        assert!(matches!(state.history.last(), Some(Action::PlaceMarker(_))));
+        assert_state_sources_agree(&state);
+    }
+
+    #[test]
+    fn test_state_sources_agree_in_game() {
+        let positions = fixed_game();
+        // every check replays all changes since the start, a desync shows up in any later one
+        for state in positions.iter().step_by(20).chain(positions.last()) {
+            assert_state_sources_agree(state);
+        }
+    }
+
+    #[test]
+    fn test_pretty_print_summary() {
+        let mut state = State::new(Board::new());
+        state.place_ring(&Player::White, &HexCoord::new(0, 0));
+        state.place_marker(&Player::Black, &HexCoord::new(1, 1));
+        let printed = state.pretty_print();
+        assert!(printed.contains("W: 1 rings, 0 markers / B: 0 rings, 1 markers"));
+        let lines: Vec<&str> = printed.lines().collect();
+        let board_rows = lines[1..lines.len() - 2].concat();
+        assert_eq!(board_rows.matches('W').count(), 1);
+        assert_eq!(board_rows.matches('b').count(), 1);
+        assert_eq!(board_rows.matches('.').count(), state.board.board_coords().len() - 2);
+    }
+
+    #[test]
+    fn test_runs_for_opponent() {
+        let mut state = State::new(Board::new());
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 0));
+        }
+        state.board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        state.board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, -2));
+        state.set_turn(Player::White, Phase::MoveRing(HexCoord::new(0, -2)));
+        assert!(state.runs_for(&Player::Black).is_empty());
+
+        state
+            .apply(&Action::from(MoveRing {
+                player: Player::White,
+                from: HexCoord::new(0, -2),
+                to: HexCoord::new(0, 1),
+            }))
+            .unwrap();
+        assert_eq!(state.runs_for(&Player::Black).len(), 1);
+        assert!(state.runs_for(&Player::White).is_empty());
+        assert_eq!(state.current_player, Player::Black);
+    }
+
+    #[test]
+    fn test_restart_keeps_blitz_rules() {
+        let options = GameOptions {
+            run_length: 4,
+            ..GameOptions::blitz()
+        };
+        let mut state = State::with_options(Board::new(), options);
+        state.place_ring(&Player::White, &HexCoord::new(0, 0));
+        state.points_black = 1;
+        state.restart();
+
+        assert_eq!(state.options, options);
+        assert_eq!(state.board.run_length(), 4);
+        assert_eq!(state.board.rings().count(), 0);
+        assert_eq!(state.get_score(&Player::Black), 0);
+
+        let c = HexCoord::new(2, 3);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &c);
+        state.set_turn(Player::White, Phase::RemoveRing);
+        state
+            .apply(&Action::from(RemoveRing {
+                coord: c,
+                player: Player::White,
+            }))
+            .unwrap();
+        assert_eq!(state.won_by(), Some(Player::White));
+        assert_eq!(state.win_score(), 1);
+
+        // the same removal is only the first of three in the standard game
+        let mut standard = State::new(Board::new());
+        standard.board.place_unchecked(&Piece::Ring(Player::White), &c);
+        standard.set_turn(Player::White, Phase::RemoveRing);
+        standard
+            .apply(&Action::from(RemoveRing {
+                coord: c,
+                player: Player::White,
+            }))
+            .unwrap();
+        assert_eq!(standard.win_score(), 3);
+        assert_eq!(standard.get_score(&Player::White), 1);
+        assert_eq!(standard.won_by(), None);
+    }
+
+    #[test]
+    fn test_legal_moves_grouped_by_ring() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Marker(Player::White), &from);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 2));
+        state.set_turn(Player::White, Phase::MoveRing(from));
+
+        let grouped = state.legal_moves_grouped();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[&from].len(), state.legal_moves().len());
+
+        state.set_turn(Player::White, Phase::PlaceRing);
+        assert_eq!(
+            state.legal_moves_grouped().len(),
+            state.legal_moves().len()
+        );
+    }
+
+    #[test]
+    fn test_runs_excluding_scheduled_removal() {
+        let (mut state, run, _) = state_with_white_run();
+        state.board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
+        state.compute_runs();
+        assert_eq!(state.current_player_runs().len(), 2);
+
+        let remaining = state.current_player_runs_excluding(&run);
+        assert!(remaining.is_empty());
+
+        let remaining = state.current_player_runs_excluding(&run[..1]);
+        assert_eq!(remaining.len(), 1);
+        assert!(!remaining[0].contains(&run[0]));
+    }
+
+    #[test]
+    fn test_resolve_run_illegal_ring_rolls_back() {
+        let (mut state, run, _) = state_with_white_run();
+
+        let err = state.resolve_run(&run, &HexCoord::new(-2, -3));
+        assert_eq!(err, Err(IllegalMoveError::NotAllowed(HexCoord::new(-2, -3))));
+        assert_eq!(state.get_score(&Player::White), 0);
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+        assert_eq!(state.board.markers().count(), 5);
+        assert!(state.history.is_empty());
+    }
+
+    #[test]
+    fn test_execute_undo_redo_roundtrip() {
+        let positions = fixed_game();
+        let mut covered = std::collections::HashSet::new();
+
+        for state in positions.iter() {
+            for action in state.legal_moves() {
+                let mut done = state.clone();
+                action.execute(&mut done);
+                let mut copy = done.clone();
+                assert!(copy.undo());
+                assert!(copy == *state, "undo of {:?} diverged", action);
+                assert!(copy.redo());
+                assert!(copy == done, "redo of {:?} diverged", action);
+                assert!(copy.redo_stack.is_empty());
+                covered.insert(std::mem::discriminant(&action));
+            }
+        }
+        // every action type, deferring a move included
+        assert_eq!(covered.len(), 6);
+
+        // redo walks back through several undos, a fresh action drops what is left
+        let mut state = positions.last().unwrap().clone();
+        assert!(state.undo() && state.undo());
+        assert!(state.redo());
+        assert_eq!(state.redo_stack.len(), 1);
+        let fresh = state.legal_moves()[0].clone();
+        fresh.execute(&mut state);
+        assert!(state.redo_stack.is_empty());
+        assert!(!state.redo());
+    }
+
+    // black's only ring is walled in by white rings, white is about to move from (3, 0)
+    fn immobilized_black(options: GameOptions) -> State {
+        let mut state = State::with_options(Board::new(), options);
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 0));
+        for c in [(0, 1), (1, 1), (1, 0), (0, -1), (-1, -1), (-1, 0)] {
+            state.board
+                .place_unchecked(&Piece::Ring(Player::White), &HexCoord::from(c));
+        }
+        let from = HexCoord::new(3, 0);
+        state.board.place_unchecked(&Piece::Marker(Player::White), &from);
+        state.set_turn(Player::White, Phase::MoveRing(from));
+        state
+    }
+
+    #[test]
+    fn test_blocked_player_loses() {
+        let mut state = immobilized_black(GameOptions::default());
+        let before = state.clone();
+        let action = state.legal_moves()[0].clone();
+        state.apply(&action).unwrap();
+        assert!(state.is_terminal());
+        assert_eq!(state.won_by(), Some(Player::White));
+        assert!(state.legal_moves().is_empty());
+
+        assert!(state.undo());
+        assert!(state == before);
+        assert!(!state.is_terminal());
+
+        // a walled in ring cannot take a marker either
+        state.set_turn(Player::Black, Phase::PlaceMarker);
+        assert!(state.is_blocked());
+        let place_marker = Action::from(PlaceMarker {
+            coord: HexCoord::new(0, 0),
+        });
+        assert!(!place_marker.is_legal(&state));
+
+        let options = GameOptionsBuilder::new()
+            .blocked_player_loses(false)
+            .try_build()
+            .unwrap();
+        let mut state = immobilized_black(options);
+        state.apply(&action).unwrap();
+        assert!(state.is_terminal());
+        assert!(state.is_draw());
+        assert_eq!(state.won_by(), None);
+    }
+
+    #[test]
+    fn test_apply_all_rolls_back() {
+        let mut state = State::from_notation("R e5\nR e7\n").unwrap();
+        let before = state.to_board_array();
+
+        let mut probe = state.clone();
+        let mut actions = vec![];
+        for _ in 0..3 {
+            let action = probe.legal_moves()[0].clone();
+            probe.apply(&action).unwrap();
+            actions.push(action);
+        }
+        // placing a ring on an occupied field
+        actions.insert(2, actions[0].clone());
+
+        assert_eq!(
+            state.apply_all(&actions),
+            Err((2, IllegalMoveError::NotAllowed(actions[0].coord())))
+        );
+        assert_eq!(state.to_board_array(), before);
+        assert_eq!(state.history.len(), 2);
+        assert_eq!(state.current_player, Player::White);
+
+        actions.remove(2);
+        assert_eq!(state.apply_all(&actions), Ok(()));
+        assert_eq!(state.to_board_array(), probe.to_board_array());
+    }
+
+    #[test]
+    fn test_resolve_run_wrong_phase() {
+        let (mut state, run, ring) = state_with_white_run();
+        state.set_phase(Phase::PlaceMarker);
+
+        assert_eq!(
+            state.resolve_run(&run, &ring),
+            Err(IllegalMoveError::WrongPhase(Phase::PlaceMarker))
+        );
+        assert_eq!(
+            state.resolve_run(&run[1..], &ring),
+            Err(IllegalMoveError::WrongPhase(Phase::PlaceMarker))
+        );
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        let mut state = State::new(Board::new());
+        assert!(!state.accept_draw(&Player::Black));
+
+        state.offer_draw(&Player::White);
+        assert!(!state.accept_draw(&Player::White));
+        assert!(state.accept_draw(&Player::Black));
+        assert!(state.is_draw());
+        assert!(state.won_by().is_none());
+        assert!(state.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut state = State::new(Board::new());
+        state.resign(&Player::Black);
+        assert_eq!(state.won_by(), Some(Player::White));
+        assert_eq!(state.resigned_by, Some(Player::Black));
+
+        // a finished game stays finished
+        state.resign(&Player::White);
+        assert_eq!(state.won_by(), Some(Player::White));
+        state.restart();
+        assert!(state.resigned_by.is_none());
+    }
+
+    #[test]
+    fn test_draw_offer_lapses_on_move() {
+        let mut state = State::new(Board::new());
+        state.offer_draw(&Player::White);
+
+        let action = Action::from(PlaceRing {
+            coord: HexCoord::new(0, 0),
+        });
+        action.execute(&mut state);
+
+        assert!(state.draw_offered_by.is_none());
+        assert!(!state.accept_draw(&Player::Black));
+        assert!(!state.is_draw());
+    }
+
+    #[test]
+    fn test_board_array_invalid() {
+        let state = State::new(Board::new());
+        let mut array = state.to_board_array();
+        assert!(State::board_from_array(&array[1..]).is_none());
+
+        array[BOARD_ARRAY_HEADER_LEN] = 7;
+        assert!(State::board_from_array(&array).is_none());
     }
 }
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// entries beyond this are dropped wholesale, old positions are rarely revisited
+const TABLE_CAPACITY: usize = 1 << 18;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // the score is at least this, the search failed high
+    Lower,
+    // the score is at most this, the search failed low
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableEntry {
+    pub depth: u32,
+    pub score: f32,
+    pub bound: Bound,
+}
+
+// search results keyed by zobrist hash, see core::zobrist
+#[derive(Default)]
+pub struct TranspositionTable {
+    entries: HashMap<u64, TableEntry>,
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // a stored score usable for a search of depth within alpha..beta
+    pub fn probe(&self, hash: u64, depth: u32, alpha: f32, beta: f32) -> Option<f32> {
+        let entry = self.entries.get(&hash)?;
+        if entry.depth < depth {
+            return None;
+        }
+        match entry.bound {
+            Bound::Exact => Some(entry.score),
+            Bound::Lower if entry.score >= beta => Some(entry.score),
+            Bound::Upper if entry.score <= alpha => Some(entry.score),
+            _ => None,
+        }
+    }
+
+    // keeps the deeper result when the position is already stored
+    pub fn store(&mut self, hash: u64, depth: u32, score: f32, bound: Bound) {
+        if self.entries.len() >= TABLE_CAPACITY {
+            self.entries.clear();
+        }
+        let entry = TableEntry { depth, score, bound };
+        match self.entries.get(&hash) {
+            Some(old) if old.depth > depth => (),
+            _ => {
+                self.entries.insert(hash, entry);
+            }
+        }
+    }
+}
+
+// a table the lazy smp search threads share, clones refer to the same entries
+#[derive(Clone, Default)]
+pub struct SharedTable {
+    table: Arc<Mutex<TranspositionTable>>,
+}
+
+impl SharedTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn probe(&self, hash: u64, depth: u32, alpha: f32, beta: f32) -> Option<f32> {
+        self.lock().probe(hash, depth, alpha, beta)
+    }
+
+    pub fn store(&self, hash: u64, depth: u32, score: f32, bound: Bound) {
+        self.lock().store(hash, depth, score, bound);
+    }
+
+    // a thread panicking mid store leaves at worst one entry behind, the rest stays usable
+    fn lock(&self) -> std::sync::MutexGuard<'_, TranspositionTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_probe_respects_depth_and_bounds() {
+        let mut table = TranspositionTable::new();
+        table.store(1, 3, 10., Bound::Exact);
+        table.store(2, 3, 10., Bound::Lower);
+        table.store(3, 3, 10., Bound::Upper);
+
+        assert_eq!(table.probe(1, 3, 0., 20.), Some(10.));
+        assert_eq!(table.probe(1, 4, 0., 20.), None);
+        assert_eq!(table.probe(2, 2, 0., 20.), None);
+        assert_eq!(table.probe(2, 2, 0., 5.), Some(10.));
+        assert_eq!(table.probe(3, 2, 0., 20.), None);
+        assert_eq!(table.probe(3, 2, 15., 20.), Some(10.));
+
+        // shallower results do not replace deeper ones
+        table.store(1, 1, 0., Bound::Exact);
+        assert_eq!(table.probe(1, 1, 0., 20.), Some(10.));
+    }
+
+    #[test]
+    fn test_shared_table_clones_share_entries() {
+        let table = SharedTable::new();
+        let other = table.clone();
+        std::thread::spawn(move || other.store(1, 3, 10., Bound::Exact))
+            .join()
+            .unwrap();
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.probe(1, 3, 0., 20.), Some(10.));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::core::ai::{HeuristicWeights, SimpleAI};
+use crate::core::board::Board;
+use crate::core::command::Command;
+use crate::core::entities::Player;
+use crate::core::rng::Rng;
+use crate::core::state::{Phase, State};
+
+// long enough for a full game, games still running after max_plies count as a draw
+pub const MAX_PLIES: usize = 400;
+
+// outcome of a match from the view of weight set a
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    pub wins_a: u32,
+    pub wins_b: u32,
+    pub draws: u32,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> u32 {
+        self.wins_a + self.wins_b + self.draws
+    }
+
+    // share of the points won by a, a draw counts half
+    pub fn win_rate_a(&self) -> f32 {
+        match self.games() {
+            0 => 0.,
+            n => (self.wins_a as f32 + self.draws as f32 / 2.) / n as f32,
+        }
+    }
+}
+
+// plays weight set a against b at a fixed depth. games come in pairs starting from the same
+// random ring placement with colors swapped, the same seed replays the same match
+pub fn play_match(
+    a: HeuristicWeights,
+    b: HeuristicWeights,
+    depth: u32,
+    games: u32,
+    max_plies: usize,
+    seed: u64,
+) -> MatchResult {
+    let mut rng = Rng::new(seed);
+    let mut result = MatchResult::default();
+    let mut opening = random_opening(&mut rng);
+    for game in 0..games {
+        if game % 2 == 0 && game > 0 {
+            opening = random_opening(&mut rng);
+        }
+        let a_color = if game % 2 == 0 { Player::White } else { Player::Black };
+        match play_game(opening.clone(), a, b, a_color, depth, max_plies) {
+            Some(winner) if winner == a_color => result.wins_a += 1,
+            Some(_) => result.wins_b += 1,
+            None => result.draws += 1,
+        }
+    }
+    result
+}
+
+// all rings placed at random, the searched part of the game starts from here
+fn random_opening(rng: &mut Rng) -> State {
+    let mut state = State::new(Board::new());
+    while state.at_phase(&Phase::PlaceRing) {
+        let moves = state.legal_moves();
+        moves[rng.below(moves.len())].execute(&mut state);
+    }
+    state
+}
+
+// the winner, None for a draw
+fn play_game(
+    mut state: State,
+    a: HeuristicWeights,
+    b: HeuristicWeights,
+    a_color: Player,
+    depth: u32,
+    max_plies: usize,
+) -> Option<Player> {
+    let mut ai_a = SimpleAI::with_weights(a_color, depth, a);
+    let mut ai_b = SimpleAI::with_weights(a_color.other(), depth, b);
+    while state.history.len() < max_plies && !state.is_terminal() {
+        let ai = if state.current_player == a_color {
+            &mut ai_a
+        } else {
+            &mut ai_b
+        };
+        ai.turn(&mut state)?;
+    }
+    state.won_by()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_match_is_reproducible() {
+        let a = HeuristicWeights::default();
+        let b = HeuristicWeights {
+            markers: 20.,
+            ..Default::default()
+        };
+        // a few moves past the ring placement are enough to tell games apart
+        let max_plies = 40;
+        let result = play_match(a, b, 0, 2, max_plies, 7);
+        assert_eq!(result.games(), 2);
+        assert!((0. ..=1.).contains(&result.win_rate_a()));
+
+        // the same seed replays the same games, here with the sets swapped
+        let mirrored = play_match(b, a, 0, 2, max_plies, 7);
+        assert_eq!((mirrored.wins_a, mirrored.wins_b), (result.wins_b, result.wins_a));
+    }
+}
@@ -0,0 +1,104 @@
+use super::board::piece_index;
+use super::coord::HexCoord;
+use super::entities::Player;
+use super::packed::pack_coord;
+use super::state::{Phase, State};
+
+// feature ranges, pieces take 4 keys per packed coordinate
+const PIECE_KEYS: u64 = 0;
+const BLACK_TO_MOVE_KEY: u64 = 1 << 10;
+const PHASE_KEYS: u64 = 1 << 11;
+const MOVE_RING_FROM_KEYS: u64 = 1 << 12;
+const WHITE_SCORE_KEYS: u64 = 1 << 13;
+const BLACK_SCORE_KEYS: u64 = 1 << 14;
+const RESOLVING_OPPONENT_RUN_KEY: u64 = 1 << 15;
+
+// splitmix64, the keys are derived on the fly instead of stored in a table
+fn key(feature: u64) -> u64 {
+    let mut z = feature.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn coord_feature(coord: &HexCoord) -> u64 {
+    pack_coord(coord) as u64
+}
+
+fn phase_key(phase: &Phase) -> u64 {
+    let index = match phase {
+        Phase::PlaceRing => 0,
+        Phase::PlaceMarker => 1,
+        Phase::MoveRing(_) => 2,
+        Phase::RemoveRun => 3,
+        Phase::RemoveRing => 4,
+        Phase::PlayerWon(Player::White) => 5,
+        Phase::PlayerWon(Player::Black) => 6,
+        Phase::Draw => 7,
+    };
+    let from = match phase {
+        Phase::MoveRing(from) => key(MOVE_RING_FROM_KEYS + coord_feature(from)),
+        _ => 0,
+    };
+    key(PHASE_KEYS + index) ^ from
+}
+
+// position hash over pieces, player to move, phase and scores
+pub fn hash(state: &State) -> u64 {
+    let pieces = state
+        .board
+        .pieces()
+        .fold(0, |h, (c, p)| h ^ key(PIECE_KEYS + 4 * coord_feature(c) + piece_index(p) as u64));
+    let player = match state.player() {
+        Player::White => 0,
+        Player::Black => key(BLACK_TO_MOVE_KEY),
+    };
+    // who passes the turn after a removal depends on who made the run
+    let resolving = match state.phase() {
+        Phase::RemoveRun | Phase::RemoveRing
+            if state.last_mover().is_some_and(|m| m != state.player()) =>
+        {
+            key(RESOLVING_OPPONENT_RUN_KEY)
+        }
+        _ => 0,
+    };
+    let (white, black) = state.scores();
+    pieces
+        ^ resolving
+        ^ player
+        ^ phase_key(&state.phase())
+        ^ key(WHITE_SCORE_KEYS + white as u64)
+        ^ key(BLACK_SCORE_KEYS + black as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::board::Board;
+    use crate::core::notation::notation_to_action;
+
+    #[test]
+    fn test_transpositions_hash_equal() {
+        let play = |moves: &[&str]| {
+            let mut state = State::new(Board::new());
+            for m in moves {
+                let action = notation_to_action(&state, m).unwrap();
+                state.apply(&action).unwrap();
+            }
+            state
+        };
+        let a = play(&["R e5", "R e7", "R f7", "R g7"]);
+        let b = play(&["R f7", "R g7", "R e5", "R e7"]);
+        let c = play(&["R e7", "R e5", "R f7", "R g7"]);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(hash(&a), hash(&c));
+
+        let mut d = a.clone();
+        d.set_turn(Player::Black, d.phase());
+        assert_ne!(hash(&a), hash(&d));
+
+        let mut e = a.clone();
+        e.points_white = 1;
+        assert_ne!(hash(&a), hash(&e));
+    }
+}
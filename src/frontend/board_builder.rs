@@ -18,26 +18,32 @@ use super::{
     element::{Element, Property},
     elements::{
         allowed_moves_indicator::AllowedMovesIndicator, field_marker::FieldMarker,
+        focus_ring::FocusRing,
         run_indicator::RunIndicator, token::TokenBuilder,
     },
     presenter::Presenter,
 };
 
 pub struct BoardBuilder {
-    white_ring_slots: [Point; 3],
-    black_ring_slots: [Point; 3],
+    board_radius: f32,
     board: Board,
+    human: Player,
 }
 
 impl BoardBuilder {
     pub fn new(board_radius: f32, font: Font) -> Self {
         Self {
-            white_ring_slots: Self::create_ring_slots(Point(-board_radius, -board_radius), 1.),
-            black_ring_slots: Self::create_ring_slots(Point(board_radius, board_radius), -1.),
+            board_radius,
             board: Board::new(board_radius, font, -2),
+            human: Player::White,
         }
     }
 
+    pub fn set_human_player(&mut self, human: Player) {
+        self.human = human;
+        self.board.set_human_player(human);
+    }
+
     pub fn create_board_from_state(
         &mut self,
         state: &State,
@@ -49,43 +55,38 @@ impl BoardBuilder {
         self.create_static_elements(state, presenter);
 
         if interactive {
-            presenter.schedule_event(Event::PlayerTurn(state.current_player, state.current_phase));
+            presenter.schedule_event(Event::PlayerTurn(state.player(), state.phase()));
             self.trigger_animation_events(state, presenter);
 
-            if state.current_player == Player::White {
+            if state.player() == self.human {
                 self.create_interactive_elements(state, presenter);
             }
         }
     }
 
-    fn create_ring_slots(pos: Point, dist: f32) -> [Point; 3] {
-        [
-            pos,
-            Point(pos.0 + dist, pos.1),
-            Point(pos.0 + 2. * dist, pos.1),
-        ]
-    }
-
-    fn ring_slots(&self, player: Player) -> &[Point; 3] {
-        match player {
-            Player::White => &self.white_ring_slots,
-            Player::Black => &self.black_ring_slots,
-        }
+    // where the i-th ring won by player is put, in a row from the player's corner, as long as
+    // the win score of the options asks for
+    fn ring_slot(&self, player: Player, i: usize) -> Point {
+        let (r, dist) = match player {
+            Player::White => (-self.board_radius, 1.),
+            Player::Black => (self.board_radius, -1.),
+        };
+        Point(r + i as f32 * dist, r)
     }
 
     fn create_static_elements(&mut self, state: &State, presenter: &mut Presenter) {
         let runs = state.current_player_runs();
 
         for player in [Player::White, Player::Black] {
-            add_won_rings(self.ring_slots(player), &player, &state, presenter);
+            add_won_rings(|i| self.ring_slot(player, i), &player, &state, presenter);
 
             for c in state.board.player_rings(player) {
-                add_ring_element(*c, player, &state, presenter);
+                add_ring_element(*c, player, self.human, &state, presenter);
             }
 
             for c in state.board.player_markers(player) {
                 let marker_part_of_run = runs.iter().flatten().find(|&x| x == c).is_some();
-                if !(state.current_phase == Phase::RemoveRun && marker_part_of_run) {
+                if !(state.phase() == Phase::RemoveRun && marker_part_of_run) {
                     let token = TokenBuilder::new()
                         .marker(player)
                         .coord(*c)
@@ -95,13 +96,29 @@ impl BoardBuilder {
             }
         }
 
-        if state.current_phase == Phase::RemoveRun {
-            add_run_indicators(&runs, state, presenter);
+        if state.phase() == Phase::RemoveRun {
+            add_run_indicators(&runs, self.human, state, presenter);
+        }
+
+        // runs the bot resolves once the human is done with theirs
+        if state.player() == self.human
+            && matches!(state.phase(), Phase::RemoveRun | Phase::RemoveRing)
+        {
+            add_opponent_run_indicators(state.runs_for(&self.human.other()), presenter);
         }
     }
 
     fn trigger_animation_events(&mut self, state: &State, presenter: &mut Presenter) {
-        for i in &state.last_state_change() {
+        // a removed ring was won if the removal phase moved on to the next turn or run
+        let ring_won = state.last_state_change().iter().any(|c| {
+            matches!(
+                c,
+                StateChange::PhaseChanged(Phase::PlaceMarker | Phase::RemoveRun)
+            )
+        });
+
+        let changes = state.last_state_change();
+        for i in &changes {
             match i {
                 StateChange::MarkerFlipped(coord) => {
                     if let Some(player) = state.board.belongs_to(&coord) {
@@ -120,9 +137,9 @@ impl BoardBuilder {
                     presenter.schedule_event(Event::RemoveMarker(*coord));
                 }
                 StateChange::RingRemoved(player, coord) => {
-                    if state.current_phase == Phase::PlaceMarker {
+                    if ring_won {
                         let score = state.get_score(player);
-                        let slot_pt = self.ring_slots(*player)[score - 1];
+                        let slot_pt = self.ring_slot(*player, score - 1);
                         // TODO THIS IS WRONG
                         let token = TokenBuilder::new()
                             .ring(*player)
@@ -133,6 +150,17 @@ impl BoardBuilder {
                         presenter.schedule_event(Event::MoveRing(Point::from(*coord), slot_pt));
                     }
                 }
+                StateChange::ScoreReverted(player) => {
+                    // the taken back ring returns from its slot to where it was removed
+                    let placed = changes.iter().find_map(|c| match c {
+                        StateChange::RingPlaced(p, coord) if p == player => Some(*coord),
+                        _ => None,
+                    });
+                    let slot_pt = self.ring_slot(*player, state.get_score(player));
+                    if let Some(coord) = placed {
+                        presenter.schedule_event(Event::MoveRing(slot_pt, Point::from(coord)));
+                    }
+                }
                 _ => (),
             }
         }
@@ -141,13 +169,20 @@ impl BoardBuilder {
     fn create_interactive_elements(&mut self, state: &State, presenter: &mut Presenter) {
         state.legal_moves().iter().for_each(|action| {
             let mut marker = FieldMarker::new(action.coord());
-            if state.current_phase == Phase::PlaceRing {
+            if state.phase() == Phase::PlaceRing {
                 marker.set_visible(false);
             }
-            presenter.add_element(Box::new(marker));
+            presenter.add_element_to_layer(Box::new(marker), LEGAL_MOVES_LAYER);
         });
+        let targets = state.legal_moves().iter().map(|a| a.coord()).collect();
+        let mut focus_ring = FocusRing::new(targets);
+        // after scoring, suggest giving up the least mobile ring
+        if let Some(suggested) = state.suggested_ring_removal() {
+            focus_ring = focus_ring.with_focus(suggested);
+        }
+        presenter.add_element(Box::new(focus_ring));
 
-        match state.current_phase {
+        match state.phase() {
             Phase::PlaceMarker => {
                 add_marker_at_pointer(&Point(0., 0.), state, presenter);
             }
@@ -156,19 +191,25 @@ impl BoardBuilder {
             }
             Phase::MoveRing(from) => {
                 add_ring_at_pointer(&Point(0., 0.), state, presenter);
-                add_legal_moves_indicator(&from, presenter);
+                add_legal_moves_indicator(&from, state.player(), presenter);
             }
             _ => (),
         }
     }
 }
 
-fn add_ring_element(c: HexCoord, player: Player, state: &State, presenter: &mut Presenter) {
+fn add_ring_element(
+    c: HexCoord,
+    player: Player,
+    human: Player,
+    state: &State,
+    presenter: &mut Presenter,
+) {
     let mut builder = TokenBuilder::new();
     builder.ring(player).coord(c).z_value(RING_Z_VALUE);
-    if player == Player::White
-        && state.current_player == Player::White
-        && state.current_phase == Phase::RemoveRing
+    if player == human
+        && state.player() == human
+        && state.phase() == Phase::RemoveRing
     {
         builder.add_property(Property::Clickable);
         builder.add_property(Property::Hoverable);
@@ -177,7 +218,12 @@ fn add_ring_element(c: HexCoord, player: Player, state: &State, presenter: &mut
     presenter.add_element(Box::new(builder.build_animated()));
 }
 
-fn add_run_indicators(r: &Vec<Vec<HexCoord>>, state: &State, presenter: &mut Presenter) {
+fn add_run_indicators(
+    r: &Vec<Vec<HexCoord>>,
+    human: Player,
+    state: &State,
+    presenter: &mut Presenter,
+) {
     let mut added_marker: HashMap<HexCoord, ElementId> = HashMap::new();
 
     for (i, r) in r.iter().enumerate() {
@@ -188,7 +234,7 @@ fn add_run_indicators(r: &Vec<Vec<HexCoord>>, state: &State, presenter: &mut Pre
             RUN_Z_VALUE + i as i32,
         ));
         run_indicator.set_coord(r[0]);
-        if state.current_player == Player::White {
+        if state.player() == human {
             run_indicator.add_property(Property::Hoverable);
             run_indicator.add_property(Property::Clickable);
         }
@@ -197,11 +243,11 @@ fn add_run_indicators(r: &Vec<Vec<HexCoord>>, state: &State, presenter: &mut Pre
         for c in r {
             if !added_marker.contains_key(&c) {
                 let mut builder = TokenBuilder::new();
-                if state.current_player == Player::White {
+                if state.player() == human {
                     builder.add_property(Property::Hoverable);
                 }
                 let token = builder
-                    .marker(state.current_player)
+                    .marker(state.player())
                     .coord(*c)
                     .add_property(Property::NoEventHandling)
                     .build();
@@ -215,9 +261,21 @@ fn add_run_indicators(r: &Vec<Vec<HexCoord>>, state: &State, presenter: &mut Pre
     }
 }
 
+fn add_opponent_run_indicators(runs: &[Vec<HexCoord>], presenter: &mut Presenter) {
+    for (i, r) in runs.iter().enumerate() {
+        let run_indicator = RunIndicator::from_segment_coords(
+            r[0],
+            *r.last().unwrap(),
+            0.5,
+            RUN_Z_VALUE + i as i32,
+        );
+        presenter.add_element_to_layer(Box::new(run_indicator), OPPONENT_RUNS_LAYER);
+    }
+}
+
 fn add_ring_at_pointer(mouse_pos: &Point, state: &State, presenter: &mut Presenter) {
     let token = TokenBuilder::new()
-        .ring(state.current_player)
+        .ring(state.player())
         .pos(*mouse_pos)
         .z_value(CURSOR_Z_VALUE)
         .alpha(0.5)
@@ -228,7 +286,7 @@ fn add_ring_at_pointer(mouse_pos: &Point, state: &State, presenter: &mut Present
 
 fn add_marker_at_pointer(mouse_pos: &Point, state: &State, presenter: &mut Presenter) {
     let token = TokenBuilder::new()
-        .marker(state.current_player)
+        .marker(state.player())
         .pos(*mouse_pos)
         .z_value(CURSOR_Z_VALUE)
         .add_property(Property::FollowMousePointer)
@@ -236,10 +294,10 @@ fn add_marker_at_pointer(mouse_pos: &Point, state: &State, presenter: &mut Prese
     presenter.add_element(Box::new(token));
 }
 
-fn add_legal_moves_indicator(from: &HexCoord, presenter: &mut Presenter) {
+fn add_legal_moves_indicator(from: &HexCoord, player: Player, presenter: &mut Presenter) {
     // ring at last position
     let token = TokenBuilder::new()
-        .ring(Player::White)
+        .ring(player)
         .coord(*from)
         .z_value(RING_Z_VALUE)
         .alpha(0.5)
@@ -251,11 +309,11 @@ fn add_legal_moves_indicator(from: &HexCoord, presenter: &mut Presenter) {
         (*from).into(),
         LEGAL_MOVE_Z_VALUE,
     ));
-    presenter.add_element(element);
+    presenter.add_element_to_layer(element, LEGAL_MOVES_LAYER);
 }
 
 fn add_won_rings(
-    ring_slots: &[Point; 3],
+    ring_slot: impl Fn(usize) -> Point,
     player: &Player,
     state: &State,
     presenter: &mut Presenter,
@@ -270,7 +328,7 @@ fn add_won_rings(
     }
 
     for i in 0..score {
-        let token = TokenBuilder::new().ring(*player).pos(ring_slots[i]).build();
+        let token = TokenBuilder::new().ring(*player).pos(ring_slot(i)).build();
         presenter.add_element(Box::new(token));
     }
 }
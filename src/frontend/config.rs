@@ -1,8 +1,26 @@
 use macroquad::prelude::*;
 
 use crate::core::entities::Player;
+// game recording, set to a file path to log every move in notation
+pub const RECORD_GAME_FILE: Option<&str> = None;
+
+// the bot keeps thinking during the human's turn, costs a cpu core while idle
+pub const AI_PONDERING: bool = false;
+
+// shows element and message counts of the presenter, toggled with F3
+pub const SHOW_DEBUG_OVERLAY: bool = false;
+pub const DEBUG_OVERLAY_KEY: KeyCode = KeyCode::F3;
+
+// shows the notation label of the field under the cursor, toggled with F4
+pub const SHOW_CURSOR_COORD: bool = false;
+pub const CURSOR_COORD_KEY: KeyCode = KeyCode::F4;
+
+// stops drawing while nothing changes, relies on the swap chain keeping its buffers
+pub const REDRAW_ONLY_WHEN_DIRTY: bool = false;
+
 // colors
 pub const GRID_LINE_COLOR: Color = Color { r: 1., g: 1., b: 1., a: 0.7};
+pub const GRID_DOT_COLOR: Color = Color { r: 1., g: 1., b: 1., a: 0.4};
 pub const BACKGROUND_COLOR: Color = WHITE;
 
 pub const BLACK_PLAYER_COLOR: Color = Color {r: 0./255., g: 92./255., b: 155./255., a: 1.};
@@ -10,8 +28,9 @@ pub const WHITE_PLAYER_COLOR: Color = WHITE;
 pub const DARK_BORDER_COLOR: Color = DARKGRAY;
 pub const LIGHT_BORDER_COLOR: Color = Color {r: 0./255., g: 40./255., b: 60./255., a: 1.};
 pub const HOVER_COLOR: Color = ORANGE;
-pub const SELECT_COLOR: Color = ORANGE;
-pub const REMOVE_COLOR: Color = ORANGE;
+pub const SELECT_COLOR: Color = GOLD;
+// hovering a ring that would be removed, should read as destructive
+pub const REMOVE_COLOR: Color = RED;
 pub const LEGAL_MOVE_MARKER_COLOR: Color = Color { r: 0., g: 0.2, b: 0.8, a: 0.7};
 
 pub const RUN_INDICATOR_COLOR: Color = Color { r: 0.98, g: 0.662, b: 0.186, a: 0.1};
@@ -27,9 +46,13 @@ pub const BUTTON_DEFAULT_COLOR: Color = WHITE;
 pub const BUTTON_HOVER_COLOR: Color = ORANGE;
 pub const BUTTON_BORDER_COLOR: Color = BLACK;
 pub const BUTTON_TEXT_COLOR: Color = DARKBLUE;
+pub const FOCUS_RING_COLOR: Color = Color { r: 1., g: 0.9, b: 0., a: 1. };
 
 // geometry
 pub const GRID_LNE_WIDTH: f32 = 0.02;
+// faint dot on every field, helps placing near the rim
+pub const SHOW_GRID_DOTS: bool = true;
+pub const GRID_DOT_RADIUS: f32 = 0.04;
 pub const MARKER_BORDER_WIDTH: f32 = 0.02;
 pub const RING_BORDER_WIDTH: f32 = 0.03;
 pub const RING_INNER_RADIUS: f32 = 0.25;
@@ -47,7 +70,14 @@ pub const RESTART_WINDOW_STATUS_FONTSIZE: f32 = 0.5;
 pub const RESTART_WINDOW_SCORE_FONTSIZE: f32 = 0.5;
 
 pub const BUTTON_FONT_SIZE: f32 = 0.25;
+pub const DEBUG_OVERLAY_FONT_SIZE: f32 = 0.2;
+pub const CURSOR_COORD_FONT_SIZE: f32 = 0.3;
+pub const DRAW_BUTTON_OFFSET: f32 = 0.6;
 pub const BUTTON_BORDER_WIDTH: f32 = 0.04;
+pub const MENU_ROW_SPACING: f32 = 1.2;
+pub const MENU_COLUMN_SPACING: f32 = 2.6;
+pub const FOCUS_RING_RADIUS: f32 = 0.3;
+pub const FOCUS_RING_WIDTH: f32 = 0.06;
 
 
 // zvalue
@@ -57,8 +87,14 @@ pub const RING_MOVE_Z_VALUE: i32 = 40;
 pub const TOKEN_Z_VALUE: i32 = 30;
 pub const RING_Z_VALUE: i32 = 40;
 pub const CURSOR_Z_VALUE: i32 = 50;
+pub const BUTTON_Z_VALUE: i32 = 60;
+pub const FOCUS_RING_Z_VALUE: i32 = 55;
 pub const RUN_Z_VALUE: i32 = 5;
 
+// layers
+pub const LEGAL_MOVES_LAYER: &str = "legal_moves";
+pub const OPPONENT_RUNS_LAYER: &str = "opponent_runs";
+
 // interaction
 pub const SNAP_DISTANCE: f32 = 0.3;
 pub const SNAP_DISTANCE_SQUARED: f32 = SNAP_DISTANCE * SNAP_DISTANCE;
@@ -67,6 +103,8 @@ pub const SNAP_DISTANCE_SQUARED: f32 = SNAP_DISTANCE * SNAP_DISTANCE;
 pub const MOVE_ANIMATION_DURATION: f64 = 0.4;
 pub const REMOVE_ANIMATION_DURATION: f64 = 0.2;
 pub const FLIP_ANIMATION_DURATION: f64 = 0.2;
+// ring moves arc sideways by this fraction of their length, 0 moves in a straight line
+pub const MOVE_ARC_RATIO: f32 = 0.15;
 
 pub fn player_color(player: &Player) -> Color {
     match player {
@@ -81,3 +119,15 @@ pub fn opponent_color(player: &Player) -> Color {
         Player::White => BLACK_PLAYER_COLOR,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interaction_colors_differ() {
+        assert_ne!(HOVER_COLOR, SELECT_COLOR);
+        assert_ne!(HOVER_COLOR, REMOVE_COLOR);
+        assert_ne!(SELECT_COLOR, REMOVE_COLOR);
+    }
+}
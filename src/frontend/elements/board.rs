@@ -6,23 +6,33 @@ use crate::{
     core::coord::{HexCoord, HexCoordF, Point},
     core::{entities::Player, game::UiAction, state::Phase},
     frontend::{
-        config::{GRID_LINE_COLOR, GRID_LNE_WIDTH},
+        config::{
+            GRID_DOT_COLOR, GRID_DOT_RADIUS, GRID_LINE_COLOR, GRID_LNE_WIDTH, SHOW_GRID_DOTS,
+        },
         element::{Element, Property},
         events::{Event, Message},
     },
 };
 
-use super::primitives::{build_grid_hull_mesh, build_grid_lines};
+use crate::core::coord::{build_grid_lines, coords_within};
+
+use super::primitives::build_grid_hull_mesh;
+
+fn build_grid_dots(radius: f32) -> Vec<Point> {
+    coords_within(radius).into_iter().map(Point::from).collect()
+}
 
 #[derive(Clone)]
 pub struct Board {
     z_value: i32,
     radius: f32,
     grid_lines: Vec<[Point; 2]>,
+    grid_dots: Vec<Point>,
     grid_border_vertices: Vec<Vertex>,
     grid_border_indices: Vec<u16>,
     status_text: String,
     font: Font,
+    human: Player,
 }
 
 impl Board {
@@ -33,13 +43,19 @@ impl Board {
             radius,
             z_value,
             grid_lines,
+            grid_dots: build_grid_dots(radius),
             grid_border_vertices,
             grid_border_indices,
             status_text: String::new(),
             font,
+            human: Player::White,
         }
     }
 
+    pub fn set_human_player(&mut self, human: Player) {
+        self.human = human;
+    }
+
     fn draw_grid(&self) {
         draw_mesh(&Mesh {
             vertices: self.grid_border_vertices.clone(),
@@ -49,6 +65,11 @@ impl Board {
         for [p0, p1] in &self.grid_lines {
             draw_line(p0.0, p0.1, p1.0, p1.1, GRID_LNE_WIDTH, GRID_LINE_COLOR);
         }
+        if SHOW_GRID_DOTS {
+            for p in &self.grid_dots {
+                draw_circle(p.0, p.1, GRID_DOT_RADIUS, GRID_DOT_COLOR);
+            }
+        }
     }
 
     fn contains(&self, _pos: Point) -> bool {
@@ -81,24 +102,24 @@ impl Element for Board {
         match message {
             //Message::MouseClicked(_) => Some(UiAction::Undo),
             Message::PlayerTurn(player, phase) => {
-                match (player, phase) {
-                    (Player::White, Phase::PlaceRing) => {
+                match (*player == self.human, phase) {
+                    (true, Phase::PlaceRing) => {
                         self.status_text = format!("Your turn, place a ring!")
                     }
-                    (Player::White, Phase::PlaceMarker) => {
+                    (true, Phase::PlaceMarker) => {
                         self.status_text = format!("Your turn, place a marker!")
                     }
-                    (Player::White, Phase::RemoveRun) => {
+                    (true, Phase::RemoveRun) => {
                         self.status_text = format!("You created a run, pick one!")
                     }
-                    (Player::White, Phase::RemoveRing) => {
-                        self.status_text = format!("Your turn, remove a ring!")
+                    (true, Phase::RemoveRing) => {
+                        self.status_text = "Run scored, give up one of your rings!".to_string()
                     }
-                    (Player::White, Phase::MoveRing(_)) => {
+                    (true, Phase::MoveRing(_)) => {
                         self.status_text = format!("Your turn, place ring!")
                     }
-                    (Player::White, _) => self.status_text = format!("Your turn!"),
-                    (Player::Black, _) => self.status_text = format!("Yinsh bot thinks ..."),
+                    (true, _) => self.status_text = format!("Your turn!"),
+                    (false, _) => self.status_text = format!("Yinsh bot thinks ..."),
                 };
                 None
             }
@@ -126,3 +147,16 @@ impl Element for Board {
         self.z_value
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_one_dot_per_field() {
+        let board = crate::core::board::Board::new();
+        let dots = build_grid_dots(board.get_radius());
+        assert_eq!(dots.len(), board.board_coords().len());
+        assert_eq!(dots.len(), 85);
+    }
+}
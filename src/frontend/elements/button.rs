@@ -0,0 +1,157 @@
+use macroquad::prelude::*;
+
+use crate::{
+    core::coord::{HexCoord, Point},
+    core::game::UiAction,
+    frontend::{
+        config::{
+            BUTTON_BORDER_COLOR, BUTTON_BORDER_WIDTH, BUTTON_DEFAULT_COLOR, BUTTON_FONT_SIZE,
+            BUTTON_HOVER_COLOR, BUTTON_TEXT_COLOR,
+        },
+        element::Element,
+        events::{Event, Message},
+        mouse::mouse_leave_enter_event,
+    },
+};
+
+pub struct Button {
+    pos: Vec2,
+    width: f32,
+    height: f32,
+    color: Color,
+    hover_color: Color,
+    default_color: Color,
+    label_text: String,
+    label_text_params: TextParams,
+    label_pos: Vec2,
+    action: Option<UiAction>,
+    z_value: i32,
+}
+
+impl Button {
+    pub fn new(
+        center_pos: Vec2,
+        left_right_margin: f32,
+        top_bottom_margin: f32,
+        font: Font,
+        label_text: &str,
+    ) -> Self {
+        let (font_size, font_scale, font_aspect) = camera_font_scale(BUTTON_FONT_SIZE);
+
+        let label_text_params = TextParams {
+            font,
+            font_size,
+            font_scale: -font_scale,
+            font_scale_aspect: -font_aspect,
+            color: BUTTON_TEXT_COLOR,
+            ..Default::default()
+        };
+
+        let label_center = get_text_center(label_text, Some(font), font_size, font_scale, 0.);
+        let label_dims = measure_text(label_text, Some(font), font_size, font_scale);
+        let (width, height) = (
+            label_dims.width + left_right_margin,
+            label_dims.height + top_bottom_margin,
+        );
+        let pos = center_pos - vec2(0.5 * width, 0.5 * height);
+
+        let label_pos = center_pos - label_center - vec2(0., label_dims.offset_y);
+
+        Self {
+            pos,
+            width,
+            height,
+            color: BUTTON_DEFAULT_COLOR,
+            default_color: BUTTON_DEFAULT_COLOR,
+            hover_color: BUTTON_HOVER_COLOR,
+            label_text: label_text.to_owned(),
+            label_pos,
+            label_text_params,
+            action: None,
+            z_value: 0,
+        }
+    }
+
+    pub fn action(mut self, action: UiAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn z_value(mut self, z_value: i32) -> Self {
+        self.z_value = z_value;
+        self
+    }
+
+    pub fn set_hovered(&mut self, hovered: bool) {
+        self.color = if hovered {
+            self.hover_color
+        } else {
+            self.default_color
+        };
+    }
+
+    pub fn contains(&self, pos: Point) -> bool {
+        pos.0 > self.pos.x
+            && pos.0 < self.pos.x + self.width
+            && pos.1 > self.pos.y
+            && pos.1 < self.pos.y + self.height
+    }
+
+    pub fn draw(&self) {
+        draw_rectangle(self.pos.x, self.pos.y, self.width, self.height, self.color);
+        draw_rectangle_lines(
+            self.pos.x,
+            self.pos.y,
+            self.width,
+            self.height,
+            BUTTON_BORDER_WIDTH,
+            BUTTON_BORDER_COLOR,
+        );
+        draw_text_ex(
+            &self.label_text,
+            self.label_pos.x,
+            self.label_pos.y,
+            self.label_text_params,
+        );
+    }
+}
+
+impl Element for Button {
+    fn render(&self) {
+        self.draw();
+    }
+
+    fn update(&mut self, message: &Message) -> Option<UiAction> {
+        match message {
+            Message::MouseClicked(_) => self.action.clone(),
+            Message::MouseEntered => {
+                self.set_hovered(true);
+                None
+            }
+            Message::MouseLeft => {
+                self.set_hovered(false);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_event(&self, event: &Event) -> Vec<Message> {
+        let mut res = vec![];
+        if let Event::Mouse(mouse_event) = event {
+            if mouse_event.left_clicked && self.contains(mouse_event.pos) {
+                res.push(Message::MouseClicked(
+                    HexCoord::closest_coord_to_point(&mouse_event.pos).0,
+                ));
+            }
+            if let Some(e) = mouse_leave_enter_event(mouse_event, |pt| self.contains(*pt)) {
+                res.push(e);
+            };
+        }
+        res
+    }
+
+    fn z_value(&self) -> i32 {
+        self.z_value
+    }
+}
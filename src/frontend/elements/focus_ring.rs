@@ -0,0 +1,137 @@
+use macroquad::prelude::*;
+
+use crate::{
+    core::coord::{HexCoord, Point},
+    core::game::UiAction,
+    frontend::{
+        config::{FOCUS_RING_COLOR, FOCUS_RING_RADIUS, FOCUS_RING_WIDTH, FOCUS_RING_Z_VALUE},
+        element::Element,
+        events::{Event, Message},
+    },
+};
+
+// keyboard focus over the legal move cells: arrows cycle, enter commits
+pub struct FocusRing {
+    targets: Vec<HexCoord>,
+    focused: Option<usize>,
+    z_value: i32,
+}
+
+impl FocusRing {
+    pub fn new(targets: Vec<HexCoord>) -> Self {
+        let mut unique: Vec<HexCoord> = vec![];
+        targets.into_iter().for_each(|c| {
+            if !unique.contains(&c) {
+                unique.push(c);
+            }
+        });
+        Self {
+            targets: unique,
+            focused: None,
+            z_value: FOCUS_RING_Z_VALUE,
+        }
+    }
+
+    pub fn focused(&self) -> Option<HexCoord> {
+        self.focused.map(|i| self.targets[i])
+    }
+
+    // starts with the given target focused, ignored if it is not one
+    pub fn with_focus(mut self, coord: HexCoord) -> Self {
+        self.focused = self.targets.iter().position(|c| *c == coord);
+        self
+    }
+
+    pub fn focus_next(&mut self) {
+        if self.targets.is_empty() {
+            return;
+        }
+        self.focused = Some(match self.focused {
+            Some(i) => (i + 1) % self.targets.len(),
+            None => 0,
+        });
+    }
+
+    pub fn focus_previous(&mut self) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let n = self.targets.len();
+        self.focused = Some(match self.focused {
+            Some(i) => (i + n - 1) % n,
+            None => n - 1,
+        });
+    }
+}
+
+impl Element for FocusRing {
+    fn render(&self) {
+        if let Some(coord) = self.focused() {
+            let pos = Point::from(coord);
+            draw_circle_lines(
+                pos.0,
+                pos.1,
+                FOCUS_RING_RADIUS,
+                FOCUS_RING_WIDTH,
+                FOCUS_RING_COLOR,
+            );
+        }
+    }
+
+    fn update(&mut self, message: &Message) -> Option<UiAction> {
+        match message {
+            Message::KeyPressed(KeyCode::Right | KeyCode::Down | KeyCode::Tab) => {
+                self.focus_next();
+                None
+            }
+            Message::KeyPressed(KeyCode::Left | KeyCode::Up) => {
+                self.focus_previous();
+                None
+            }
+            Message::KeyPressed(KeyCode::Enter | KeyCode::KpEnter) => {
+                self.focused().map(UiAction::ActionAtCoord)
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_event(&self, event: &Event) -> Vec<Message> {
+        match event {
+            Event::KeyPressed(key) => vec![Message::KeyPressed(*key)],
+            _ => vec![],
+        }
+    }
+
+    fn z_value(&self) -> i32 {
+        self.z_value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_enter_commits_focused_cell() {
+        let targets = vec![HexCoord::new(0, 1), HexCoord::new(2, 3), HexCoord::new(0, 1)];
+        let mut ring = FocusRing::new(targets);
+
+        assert_eq!(ring.update(&Message::KeyPressed(KeyCode::Enter)), None);
+
+        ring.update(&Message::KeyPressed(KeyCode::Right));
+        ring.update(&Message::KeyPressed(KeyCode::Right));
+        assert_eq!(ring.focused(), Some(HexCoord::new(2, 3)));
+        ring.update(&Message::KeyPressed(KeyCode::Right));
+        assert_eq!(ring.focused(), Some(HexCoord::new(0, 1)));
+        ring.update(&Message::KeyPressed(KeyCode::Left));
+
+        assert_eq!(
+            ring.update(&Message::KeyPressed(KeyCode::Enter)),
+            Some(UiAction::ActionAtCoord(HexCoord::new(2, 3)))
+        );
+
+        let ring = FocusRing::new(vec![HexCoord::new(0, 1), HexCoord::new(2, 3)])
+            .with_focus(HexCoord::new(2, 3));
+        assert_eq!(ring.focused(), Some(HexCoord::new(2, 3)));
+    }
+}
@@ -5,4 +5,6 @@ pub mod allowed_moves_indicator;
 pub mod token_animation;
 pub mod board;
 pub mod primitives;
-pub mod restart_window;
\ No newline at end of file
+pub mod restart_window;
+pub mod button;
+pub mod focus_ring;
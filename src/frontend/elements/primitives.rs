@@ -5,62 +5,6 @@ use std::{cmp::Eq, f32::consts::PI};
 
 use crate::core::coord::{HexCoord, HexCoordF, Point};
 
-pub fn build_grid_lines(radius: f32) -> Vec<[Point; 2]> {
-    let dx: f32 = 0.5 * (3. as f32).sqrt();
-    let mut res = Vec::new();
-
-    // diagonals
-    for dy in [-0.5 as f32, 0.5 as f32] {
-        let lambda: f32 = radius / (1. - dy.powi(2)).sqrt();
-        let (l0, l1) = ((-lambda).trunc() as i32, lambda.trunc() as i32);
-
-        for l in l0..=l1 {
-            let l = l as f32;
-            let det = (l.powi(2) * (dy.powi(2) - 1.) + radius.powi(2)).sqrt();
-            if det <= 0. {
-                continue;
-            }
-            let mut mu1 = -l * dy - det;
-            let mut mu2 = -l * dy + det;
-            if l.abs() > radius {
-                mu1 = mu1.ceil();
-                mu2 = mu2.floor();
-            } else {
-                mu1 = mu1.trunc();
-                mu2 = mu2.floor();
-            }
-
-            let vec = [Point(mu1 * dx, l + mu1 * dy), Point(mu2 * dx, l + mu2 * dy)];
-            res.push(vec);
-        }
-    }
-
-    // verticals
-    let lambda: f32 = radius * 2. / 3. * (3. as f32).sqrt();
-    let (l0, l1) = ((-lambda).trunc() as i32, lambda.trunc() as i32);
-
-    for l in l0..=l1 {
-        let l = l as f32;
-        let det = (4. * radius.powi(2) - 3. * l.powi(2)).sqrt();
-        if det <= 0. {
-            continue;
-        }
-        let mut mu1 = 0.5 * (l - det);
-        let mut mu2 = 0.5 * (l + det);
-        if l.abs() > radius {
-            mu1 = mu1.ceil();
-            mu2 = mu2.floor();
-        } else {
-            mu1 = mu1.trunc();
-            mu2 = mu2.floor();
-        }
-
-        let vec = [Point(l * dx, -0.5 * l + mu1), Point(l * dx, -0.5 * l + mu2)];
-        res.push(vec);
-    }
-    res
-}
-
 pub fn build_grid_hull_mesh(line_endpoints: &Vec<[Point; 2]>) -> (Vec<Vertex>, Vec<u16>) {
     let mut line_endpoints = line_endpoints
         .iter()
@@ -1,14 +1,13 @@
 use macroquad::prelude::*;
 
+use super::button::Button;
 use crate::{
     core::coord::{HexCoord, Point},
     core::game::UiAction,
     frontend::{
         config::{
-            BUTTON_BORDER_COLOR, BUTTON_BORDER_WIDTH, BUTTON_DEFAULT_COLOR, BUTTON_FONT_SIZE,
-            BUTTON_HOVER_COLOR, BUTTON_TEXT_COLOR, RESTART_WINDOW_SCORE_COLOR,
-            RESTART_WINDOW_SCORE_FONTSIZE, RESTART_WINDOW_STATUS_COLOR,
-            RESTART_WINDOW_STATUS_FONTSIZE, BLACK_PLAYER_COLOR,
+            RESTART_WINDOW_SCORE_COLOR, RESTART_WINDOW_SCORE_FONTSIZE,
+            RESTART_WINDOW_STATUS_COLOR, RESTART_WINDOW_STATUS_FONTSIZE, BLACK_PLAYER_COLOR,
         },
         element::Element,
         events::{Event, Message},
@@ -43,7 +42,7 @@ impl RestartWindow {
         let font_size = 1.;
 
         let button_pos = vec2(0., -1.);
-        let button = Button::new(button_pos, 1., 0.25, font);
+        let button = Button::new(button_pos, 1., 0.25, font, "PLAY AGAIN");
 
         Self {
             pos,
@@ -99,11 +98,11 @@ impl Element for RestartWindow {
         match message {
             Message::MouseClicked(_) => Some(UiAction::Restart),
             Message::MouseEntered => {
-                self.button.color = self.button.hover_color;
+                self.button.set_hovered(true);
                 None
             }
             Message::MouseLeft => {
-                self.button.color = self.button.default_color;
+                self.button.set_hovered(false);
                 None
             }
             _ => None,
@@ -154,80 +153,3 @@ fn draw_text_centered(text: &str, center_pos: Vec2, font: Font, font_size: f32,
         text_params,
     );
 }
-
-struct Button {
-    pos: Vec2,
-    width: f32,
-    height: f32,
-    color: Color,
-    hover_color: Color,
-    default_color: Color,
-    label_text: String,
-    label_text_params: TextParams,
-    label_pos: Vec2,
-    font: Font,
-}
-
-impl Button {
-    fn new(center_pos: Vec2, left_right_margin: f32, top_bottom_margin: f32, font: Font) -> Self {
-        let label_text = "PLAY AGAIN".to_owned();
-        let (font_size, font_scale, font_aspect) = camera_font_scale(BUTTON_FONT_SIZE);
-
-        let label_text_params = TextParams {
-            font,
-            font_size,
-            font_scale: -font_scale,
-            font_scale_aspect: -font_aspect,
-            color: BUTTON_TEXT_COLOR,
-            ..Default::default()
-        };
-
-        let label_center = get_text_center(&label_text, Some(font), font_size, font_scale, 0.);
-        let label_dims = measure_text(&label_text, Some(font), font_size, font_scale);
-        let (width, height) = (
-            label_dims.width + left_right_margin,
-            label_dims.height + top_bottom_margin,
-        );
-        let pos = center_pos - vec2(0.5 * width, 0.5 * height);
-
-        let label_pos = center_pos - label_center - vec2(0., label_dims.offset_y);
-
-        Self {
-            pos,
-            width,
-            height,
-            color: BUTTON_DEFAULT_COLOR,
-            default_color: BUTTON_DEFAULT_COLOR,
-            hover_color: BUTTON_HOVER_COLOR,
-            label_text,
-            label_pos,
-            label_text_params,
-            font,
-        }
-    }
-
-    fn contains(&self, pos: Point) -> bool {
-        pos.0 > self.pos.x
-            && pos.0 < self.pos.x + self.width
-            && pos.1 > self.pos.y
-            && pos.1 < self.pos.y + self.height
-    }
-
-    fn draw(&self) {
-        draw_rectangle(self.pos.x, self.pos.y, self.width, self.height, self.color);
-        draw_rectangle_lines(
-            self.pos.x,
-            self.pos.y,
-            self.width,
-            self.height,
-            BUTTON_BORDER_WIDTH,
-            BUTTON_BORDER_COLOR,
-        );
-        draw_text_ex(
-            &self.label_text,
-            self.label_pos.x,
-            self.label_pos.y,
-            self.label_text_params,
-        );
-    }
-}
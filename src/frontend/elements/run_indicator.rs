@@ -51,8 +51,10 @@ impl RunIndicator {
     pub fn from_segment_points(pt0: Point, pt1: Point, height: f32, z_value: i32) -> Self {
         let v1 = Vec2::from((pt0.0, pt0.1));
         let v2 = Vec2::from((pt1.0, pt1.1));
-        let dir = (v2 - v1).normalize();
-        let width = (v2 - v1).length();
+        let segment = pt1 - pt0;
+        let unit = segment.normalize();
+        let dir = vec2(unit.0, unit.1);
+        let width = segment.length();
         let perp = -dir.perp();
 
         let corners = [
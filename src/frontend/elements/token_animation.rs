@@ -1,10 +1,10 @@
 use std::f32::consts::PI;
 
-use crate::core::coord::Point;
+use crate::core::coord::{norm_squared, Point};
 use macroquad::prelude::*;
 
 use super::{token::{Token, TokenType, self}};
-use crate::frontend::config::{MOVE_ANIMATION_DURATION, REMOVE_ANIMATION_DURATION, FLIP_ANIMATION_DURATION};
+use crate::frontend::config::{MOVE_ANIMATION_DURATION, REMOVE_ANIMATION_DURATION, FLIP_ANIMATION_DURATION, MOVE_ARC_RATIO};
 
 
 pub trait Animation {
@@ -132,6 +132,35 @@ impl Animation for RemoveAnimation {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationSettings {
+    pub move_duration: f64,
+    // arc height of ring moves relative to their length
+    pub move_arc_ratio: f32,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        AnimationSettings {
+            move_duration: MOVE_ANIMATION_DURATION,
+            move_arc_ratio: MOVE_ARC_RATIO,
+        }
+    }
+}
+
+// eased position after the fraction t of a move, lifted sideways along a parabola peaking
+// at arc_height halfway
+fn move_position(start: Point, end: Point, arc_height: f32, t: f32) -> Point {
+    let delta = -((PI * t).cos() - 1.) / 2.;
+    let straight = start + (end - start) * delta;
+    let length = norm_squared(&(end - start)).sqrt();
+    if length == 0. {
+        return straight;
+    }
+    let normal = Point(start.1 - end.1, end.0 - start.0) / length;
+    straight + normal * (4. * arc_height * delta * (1. - delta))
+}
+
 #[derive(Clone)]
 pub struct MoveAnimation {
     start_time: f64,
@@ -139,16 +168,23 @@ pub struct MoveAnimation {
     start_pos: Point,
     end_pos: Point,
     current_pos: Point,
+    arc_height: f32,
 }
 
 impl MoveAnimation {
     pub fn new(start_pos: Point, end_pos: Point) -> Self {
+        Self::with_settings(start_pos, end_pos, AnimationSettings::default())
+    }
+
+    pub fn with_settings(start_pos: Point, end_pos: Point, settings: AnimationSettings) -> Self {
+        let length = norm_squared(&(end_pos - start_pos)).sqrt();
         MoveAnimation {
             start_time: get_time(),
-            duration: MOVE_ANIMATION_DURATION,
+            duration: settings.move_duration,
             start_pos,
             end_pos,
             current_pos: start_pos,
+            arc_height: settings.move_arc_ratio * length,
         }
     }
 
@@ -162,9 +198,8 @@ impl Animation for MoveAnimation {
         if self.finished() {
             self.current_pos = self.end_pos;
         } else {
-            let delta = (1. / self.duration * (get_time() - self.start_time)) as f32;
-            let delta = - ((PI*delta).cos() - 1.)/2.;
-            self.current_pos = self.start_pos + (self.end_pos - self.start_pos) * delta;
+            let t = (1. / self.duration * (get_time() - self.start_time)) as f32;
+            self.current_pos = move_position(self.start_pos, self.end_pos, self.arc_height, t);
         }
     }
 
@@ -176,3 +211,23 @@ impl Animation for MoveAnimation {
         get_time() - self.start_time >= self.duration
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arc_peaks_perpendicular_at_midpoint() {
+        let (start, end) = (Point(0., 0.), Point(2., 0.));
+        let height = AnimationSettings::default().move_arc_ratio * 2.;
+
+        let mid = move_position(start, end, height, 0.5);
+        assert!((mid.0 - 1.).abs() < 1e-5);
+        assert!((mid.1 - height).abs() < 1e-5);
+
+        assert_eq!(move_position(start, end, height, 0.), start);
+        let last = move_position(start, end, height, 1.);
+        assert!(norm_squared(&(last - end)) < 1e-10);
+        assert_eq!(move_position(start, end, 0., 0.5), Point(1., 0.));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{core::coord::{Point, HexCoord}, core::{entities::Player, state::Phase}};
 use super::mouse::MouseEvent;
+use macroquad::prelude::KeyCode;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum Message {
@@ -15,6 +16,7 @@ pub enum Message {
     MoveRing(Point, Point),
     RemoveMarker(HexCoord),
     PlayerTurn(Player, Phase),
+    KeyPressed(KeyCode),
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -26,5 +28,6 @@ pub enum Event {
     MoveRing(Point, Point),
     PlaceRing(Player, HexCoord),
     PlayerTurn(Player, Phase),
+    KeyPressed(KeyCode),
     Tick,
 }
@@ -3,10 +3,18 @@ use super::config::BACKGROUND_COLOR;
 use super::config::RESTART_WINDOW_BG_COLOR;
 use super::config::RESTART_WINDOW_HEIGHT;
 use super::config::RESTART_WINDOW_WIDTH;
+use super::config::BUTTON_Z_VALUE;
+use super::config::DRAW_BUTTON_OFFSET;
+use super::config::{DEBUG_OVERLAY_FONT_SIZE, DEBUG_OVERLAY_KEY, SHOW_DEBUG_OVERLAY};
+use super::config::{CURSOR_COORD_FONT_SIZE, CURSOR_COORD_KEY, SHOW_CURSOR_COORD};
+use super::config::REDRAW_ONLY_WHEN_DIRTY;
+use super::elements::button::Button;
 use super::elements::restart_window::RestartWindow;
 use super::events::Event;
-use super::mouse::MouseHandler;
+use super::menu::NewGameMenu;
+use super::mouse::{MouseEvent, MouseHandler};
 use super::presenter::Presenter;
+use super::redraw::RedrawTracker;
 use crate::core::board::*;
 use crate::core::entities::Player;
 use crate::core::game::*;
@@ -35,6 +43,10 @@ pub struct Frontend {
     pub ui_status: UiStatus,
     font: Font,
     update_request: bool,
+    debug_overlay: bool,
+    cursor_coord: bool,
+    redraw: RedrawTracker,
+    human: Player,
 }
 
 impl Frontend {
@@ -63,10 +75,55 @@ impl Frontend {
             ui_status: UiStatus::Idle,
             font,
             update_request: true,
+            debug_overlay: SHOW_DEBUG_OVERLAY,
+            cursor_coord: SHOW_CURSOR_COORD,
+            redraw: RedrawTracker::new(REDRAW_ONLY_WHEN_DIRTY),
+            human: Player::White,
             builder: BoardBuilder::new(radius, font),
         }
     }
 
+    pub fn set_layer_visible(&mut self, layer: &'static str, visible: bool) {
+        self.presenter.set_layer_visible(layer, visible);
+    }
+
+    pub fn set_redraw_only_when_dirty(&mut self, enabled: bool) {
+        self.redraw.set_enabled(enabled);
+    }
+
+    pub fn set_human_player(&mut self, human: Player) {
+        self.human = human;
+        self.builder.set_human_player(human);
+    }
+
+    // runs the new game menu for one frame, returns the configuration once started
+    pub fn tick_menu(&mut self, menu: &mut NewGameMenu) -> Option<GameConfig> {
+        self.update_window_size();
+        self.set_camera();
+        if self.update_request {
+            self.presenter.clear_all();
+            menu.create_elements(self.font, &mut self.presenter);
+            self.update_request = false;
+        }
+        self.mouse_handler.update();
+        let mouse_event = self.mouse_handler.has_message(None);
+        self.presenter.schedule_event(Event::Mouse(mouse_event));
+        self.presenter.handle_events();
+
+        clear_background(BACKGROUND_COLOR);
+        self.presenter.render();
+
+        let mut config = None;
+        for action in self.presenter.get_actions() {
+            config = config.or(menu.handle_action(&action));
+            self.update_request = true;
+        }
+        if let Some(config) = config {
+            self.set_human_player(config.human_player);
+        }
+        config
+    }
+
     fn set_camera(&self) {
         set_camera(&Camera2D {
             zoom: vec2(1. / self.width * 2., 1. / self.height * 2.),
@@ -75,14 +132,15 @@ impl Frontend {
         });
     }
 
-    fn update_window_size(&mut self) {
+    // true if the window was resized
+    fn update_window_size(&mut self) -> bool {
         let pixel_width = screen_width();
         let pixel_height = screen_height();
 
         if (pixel_width - self.pixel_width as f32).abs() < 0.5
             || (pixel_height - self.pixel_height as f32).abs() < 0.5
         {
-            return;
+            return false;
         }
 
         let min_width = 2. * self.radius + self.w_margin;
@@ -105,43 +163,132 @@ impl Frontend {
 
         self.mouse_handler =
             MouseHandler::new(self.width, self.height, self.pixel_width, self.pixel_height);
+        true
     }
 
-    fn update_if_idle(&mut self, state: &State) {
+    // true if the elements were rebuilt
+    fn update_if_idle(&mut self, state: &State) -> bool {
         if self.ui_status == UiStatus::Idle && self.update_request {
             self.presenter.clear_all();
             let mut interactive = true;
 
-            if let Some(winner) = state.won_by() {
+            let result_text = match state.won_by() {
+                Some(_) if state.resigned_by.is_some_and(|p| p != self.human) => {
+                    Some("Yinsh bot resigns!")
+                }
+                Some(player) if player == self.human => Some("Congrats, you won!"),
+                Some(_) => Some("You lost ..."),
+                None if state.is_draw() => Some("It's a draw!"),
+                None => None,
+            };
+
+            if let Some(result_text) = result_text {
                 interactive = false;
 
-                let win_text = match winner {
-                    Player::White => "Congrats, you won!",
-                    Player::Black => "You lost ...",
-                };
-                let score_text = format!("{} - {}", state.points_white, state.points_black);
+                let (white, black) = state.scores();
+                let score_text = format!("{} - {}", white, black);
 
                 self.presenter.add_element(Box::new(RestartWindow::new(
                     vec2(-0.5*RESTART_WINDOW_WIDTH, -0.5*RESTART_WINDOW_HEIGHT),
                     RESTART_WINDOW_WIDTH,
                     RESTART_WINDOW_HEIGHT,
                     RESTART_WINDOW_BG_COLOR,
-                    win_text,
+                    result_text,
                     &score_text,
                     self.font,
                     100,
                 )));
+            } else {
+                self.add_draw_button(state);
             }
             self.builder
                 .create_board_from_state(state, &mut self.presenter, interactive);
             self.update_request = false;
+            return true;
         }
+        false
+    }
+
+    fn add_draw_button(&mut self, state: &State) {
+        let label = match state.draw_offered_by {
+            Some(player) if player != self.human => "ACCEPT DRAW",
+            None if state.player() == self.human => "OFFER DRAW",
+            _ => return,
+        };
+        let action = match state.draw_offered_by {
+            Some(_) => UiAction::AcceptDraw,
+            None => UiAction::OfferDraw,
+        };
+        let pos = vec2(0., -self.radius - DRAW_BUTTON_OFFSET);
+        let button = Button::new(pos, 0.5, 0.15, self.font, label)
+            .action(action)
+            .z_value(BUTTON_Z_VALUE);
+        self.presenter.add_element(Box::new(button));
     }
 
-    fn schedule_mouse_events(&mut self, state: &State) {
+    fn mouse_event(&mut self, state: &State) -> MouseEvent {
         self.mouse_handler.update();
-        let mouse_event = self.mouse_handler.has_message(Some(&state.legal_moves()));
-        self.presenter.schedule_event(Event::Mouse(mouse_event));
+        self.mouse_handler.has_message(Some(&state.legal_moves()))
+    }
+
+    fn schedule_key_events(&mut self, key: Option<KeyCode>) {
+        if let Some(key) = key {
+            if key == DEBUG_OVERLAY_KEY {
+                self.debug_overlay = !self.debug_overlay;
+            }
+            if key == CURSOR_COORD_KEY {
+                self.cursor_coord = !self.cursor_coord;
+            }
+            self.presenter.schedule_event(Event::KeyPressed(key));
+        }
+    }
+
+    fn draw_debug_overlay(&self, messages: usize) {
+        let text = format!(
+            "elements: {}  messages: {}  subscribers: {}  {:?}  fps: {}",
+            self.presenter.element_count(),
+            messages,
+            self.presenter.subscriber_count(),
+            self.ui_status,
+            get_fps()
+        );
+        let (font_size, font_scale, font_aspect) = camera_font_scale(DEBUG_OVERLAY_FONT_SIZE);
+        let params = TextParams {
+            font: self.font,
+            font_size,
+            font_scale: -font_scale,
+            font_scale_aspect: -font_aspect,
+            color: DARKGRAY,
+            ..Default::default()
+        };
+        draw_text_ex(
+            &text,
+            -0.5 * self.width + 0.1,
+            0.5 * self.height - 0.2,
+            params,
+        );
+    }
+
+    fn draw_cursor_coord(&self) {
+        let label = match self.mouse_handler.coord_label(self.radius) {
+            Some(label) => label,
+            None => return,
+        };
+        let (font_size, font_scale, font_aspect) = camera_font_scale(CURSOR_COORD_FONT_SIZE);
+        let params = TextParams {
+            font: self.font,
+            font_size,
+            font_scale: -font_scale,
+            font_scale_aspect: -font_aspect,
+            color: DARKGRAY,
+            ..Default::default()
+        };
+        draw_text_ex(
+            &label,
+            0.5 * self.width - 0.6,
+            -0.5 * self.height + 0.2,
+            params,
+        );
     }
 
     fn handle_ui_actions(&mut self) -> UiAction {
@@ -161,7 +308,12 @@ impl Frontend {
         }
 
         ui_actions.retain(|a| match a {
-            UiAction::ActionAtCoord(_) | UiAction::Undo | UiAction::Restart => true,
+            UiAction::ActionAtCoord(_)
+            | UiAction::Undo
+            | UiAction::Redo
+            | UiAction::Restart
+            | UiAction::OfferDraw
+            | UiAction::AcceptDraw => true,
             _ => false,
         });
         if ui_actions.len() > 0 {
@@ -178,14 +330,36 @@ impl View for Frontend {
     }
 
     fn tick(&mut self, state: &State) -> UiAction {
-        self.update_window_size();
+        let resized = self.update_window_size();
         self.set_camera();
-        self.update_if_idle(state);
-        self.schedule_mouse_events(state);
+        let rebuilt = self.update_if_idle(state);
+        let mouse_event = self.mouse_event(state);
+        let key = get_last_key_pressed();
+
+        let input = key.is_some()
+            || mouse_event.left_clicked
+            || mouse_event.right_clicked
+            || mouse_event.pos != mouse_event.last_pos;
+        // the overlay shows the frame rate, it changes every frame
+        let changed = resized || rebuilt || input || self.debug_overlay;
+        if !self.redraw.frame(changed, self.ui_status) {
+            return UiAction::Idle;
+        }
+
+        self.presenter.schedule_event(Event::Mouse(mouse_event));
+        self.schedule_key_events(key);
         self.presenter.handle_events();
 
         clear_background(BACKGROUND_COLOR);
+        // messages are consumed while rendering
+        let messages = self.presenter.message_count();
         self.presenter.render();
+        if self.debug_overlay {
+            self.draw_debug_overlay(messages);
+        }
+        if self.cursor_coord {
+            self.draw_cursor_coord();
+        }
 
         self.handle_ui_actions()
     }
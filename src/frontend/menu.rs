@@ -0,0 +1,119 @@
+use macroquad::prelude::*;
+
+use crate::core::ai::Difficulty;
+use crate::core::entities::Player;
+use crate::core::game::{GameConfig, UiAction};
+use crate::core::options::GameOptions;
+
+use super::config::{BUTTON_Z_VALUE, MENU_COLUMN_SPACING, MENU_ROW_SPACING};
+use super::elements::button::Button;
+use super::presenter::Presenter;
+
+// pre-game choices of color, difficulty and rules, no board is shown until confirmed
+#[derive(Default)]
+pub struct NewGameMenu {
+    config: GameConfig,
+}
+
+impl NewGameMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn config(&self) -> GameConfig {
+        self.config
+    }
+
+    // the chosen configuration once the game is started
+    pub fn handle_action(&mut self, action: &UiAction) -> Option<GameConfig> {
+        match action {
+            UiAction::SelectPlayer(player) => self.config.human_player = *player,
+            UiAction::SelectDifficulty(difficulty) => self.config.difficulty = *difficulty,
+            UiAction::SelectOptions(options) => self.config.options = *options,
+            UiAction::StartGame => return Some(self.config),
+            _ => (),
+        }
+        None
+    }
+
+    pub fn create_elements(&self, font: Font, presenter: &mut Presenter) {
+        let players = [
+            ("PLAY WHITE", UiAction::SelectPlayer(Player::White)),
+            ("PLAY BLACK", UiAction::SelectPlayer(Player::Black)),
+        ];
+        let difficulties = [
+            ("EASY", UiAction::SelectDifficulty(Difficulty::Easy)),
+            ("MEDIUM", UiAction::SelectDifficulty(Difficulty::Medium)),
+            ("HARD", UiAction::SelectDifficulty(Difficulty::Hard)),
+        ];
+        let variants = [
+            ("STANDARD", UiAction::SelectOptions(GameOptions::default())),
+            ("BLITZ", UiAction::SelectOptions(GameOptions::blitz())),
+        ];
+
+        self.add_row(&players, 1.5 * MENU_ROW_SPACING, font, presenter);
+        self.add_row(&difficulties, 0.5 * MENU_ROW_SPACING, font, presenter);
+        self.add_row(&variants, -0.5 * MENU_ROW_SPACING, font, presenter);
+        self.add_row(
+            &[("START", UiAction::StartGame)],
+            -2. * MENU_ROW_SPACING,
+            font,
+            presenter,
+        );
+    }
+
+    fn is_selected(&self, action: &UiAction) -> bool {
+        match action {
+            UiAction::SelectPlayer(player) => self.config.human_player == *player,
+            UiAction::SelectDifficulty(difficulty) => self.config.difficulty == *difficulty,
+            UiAction::SelectOptions(options) => self.config.options == *options,
+            _ => false,
+        }
+    }
+
+    fn add_row(&self, entries: &[(&str, UiAction)], y: f32, font: Font, presenter: &mut Presenter) {
+        let x0 = -0.5 * MENU_COLUMN_SPACING * (entries.len() - 1) as f32;
+        for (i, (label, action)) in entries.iter().enumerate() {
+            let label = if self.is_selected(action) {
+                format!("[ {} ]", label)
+            } else {
+                label.to_string()
+            };
+            let pos = vec2(x0 + i as f32 * MENU_COLUMN_SPACING, y);
+            let button = Button::new(pos, 0.5, 0.15, font, &label)
+                .action(action.clone())
+                .z_value(BUTTON_Z_VALUE);
+            presenter.add_element(Box::new(button));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_menu_produces_chosen_config() {
+        let mut menu = NewGameMenu::new();
+        assert_eq!(menu.config(), GameConfig::default());
+
+        let actions = [
+            UiAction::SelectPlayer(Player::Black),
+            UiAction::SelectDifficulty(Difficulty::Easy),
+            UiAction::SelectOptions(GameOptions::blitz()),
+            UiAction::Idle,
+        ];
+        for action in actions.iter() {
+            assert_eq!(menu.handle_action(action), None);
+        }
+
+        assert_eq!(
+            menu.handle_action(&UiAction::StartGame),
+            Some(GameConfig {
+                human_player: Player::Black,
+                difficulty: Difficulty::Easy,
+                options: GameOptions::blitz(),
+            })
+        );
+    }
+}
@@ -6,4 +6,6 @@ pub mod presenter;
 pub mod events;
 pub mod elements;
 pub mod board_builder;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod menu;
+pub mod redraw;
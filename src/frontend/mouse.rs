@@ -5,6 +5,7 @@ use crate::core::command::Command;
 use crate::{
     core::coord::{HexCoord, Point},
     core::actions::Action,
+    core::notation::coord_to_label,
 };
 
 use super::config::SNAP_DISTANCE_SQUARED;
@@ -42,9 +43,13 @@ impl MouseHandler {
     }
 
     pub fn update(&mut self) {
-        self.last_pos = self.pos;
         let mp = mouse_position();
-        let (x, y) = self.pixels_to_xy(mp.0, mp.1);
+        self.set_pixel_position(mp.0, mp.1);
+    }
+
+    pub fn set_pixel_position(&mut self, px: f32, py: f32) {
+        self.last_pos = self.pos;
+        let (x, y) = self.pixels_to_xy(px, py);
         self.pos = Point(x, y);
     }
 
@@ -73,6 +78,13 @@ impl MouseHandler {
         }
     }
 
+    // notation label of the field under the cursor, None off the board
+    pub fn coord_label(&self, board_radius: f32) -> Option<String> {
+        self.to_coord(Some(SNAP_DISTANCE_SQUARED))
+            .filter(|c| c.is_on_board(board_radius))
+            .map(|c| coord_to_label(&c))
+    }
+
     pub fn to_legal_field(
         &self,
         legal_moves: &Vec<Action>,
@@ -114,3 +126,25 @@ where
     }
     msg
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pixel_to_coord_label() {
+        let mut handler = MouseHandler::new(10., 10., 100, 100);
+
+        handler.set_pixel_position(50., 50.);
+        assert_eq!(handler.coord_label(4.7), Some("f6".to_string()));
+
+        // one unit up on screen is one field north
+        handler.set_pixel_position(50., 40.);
+        assert_eq!(handler.coord_label(4.7), Some("f7".to_string()));
+
+        handler.set_pixel_position(52., 45.);
+        assert_eq!(handler.coord_label(4.7), None);
+        handler.set_pixel_position(0., 0.);
+        assert_eq!(handler.coord_label(4.7), None);
+    }
+}
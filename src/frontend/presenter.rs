@@ -1,4 +1,4 @@
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 use crate::{core::game::UiAction};
@@ -24,6 +24,8 @@ pub struct Presenter {
     subscribers: HashMap<ElementId, Vec<ElementId>>,
     actions: Vec<UiAction>,
     events: Vec<Event>,
+    layers: HashMap<ElementId, &'static str>,
+    hidden_layers: HashSet<&'static str>,
 }
 
 impl Presenter {
@@ -34,6 +36,8 @@ impl Presenter {
             subscribers: HashMap::new(),
             actions: vec![],
             events: vec![],
+            layers: HashMap::new(),
+            hidden_layers: HashSet::new(),
         }
     }
 
@@ -49,6 +53,41 @@ impl Presenter {
         id
     }
 
+    pub fn add_element_to_layer(
+        &mut self,
+        element: Box<dyn Element>,
+        layer: &'static str,
+    ) -> ElementId {
+        let id = self.add_element(element);
+        self.layers.insert(id, layer);
+        id
+    }
+
+    // layer visibility survives clear_all, so hidden layers stay hidden across rebuilds
+    pub fn set_layer_visible(&mut self, layer: &'static str, visible: bool) {
+        if visible {
+            self.hidden_layers.remove(layer);
+        } else {
+            self.hidden_layers.insert(layer);
+        }
+    }
+
+    pub fn is_layer_visible(&self, layer: &str) -> bool {
+        !self.hidden_layers.contains(layer)
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.messages.values().map(|m| m.len()).sum()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.values().map(|s| s.len()).sum()
+    }
+
     pub fn get_actions(&self) -> Vec<UiAction> {
         self.actions.clone()
     }
@@ -59,6 +98,7 @@ impl Presenter {
         self.subscribers.clear();
         self.actions.clear();
         self.events.clear();
+        self.layers.clear();
     }
 
     pub fn schedule_event(&mut self, event: Event) {
@@ -127,7 +167,16 @@ impl Presenter {
     }
 
     fn render_elements(&self) {
-        let mut sorted_elements: Vec<&Box<dyn Element>> = self.elements.values().collect();
+        let mut sorted_elements: Vec<&Box<dyn Element>> = self
+            .elements
+            .iter()
+            .filter(|(id, _)| {
+                self.layers
+                    .get(id)
+                    .is_none_or(|layer| self.is_layer_visible(layer))
+            })
+            .map(|(_, e)| e)
+            .collect();
         sorted_elements.sort_by(|a, b| a.z_value().cmp(&b.z_value()));
         sorted_elements.iter().for_each(|e| e.render());
     }
@@ -194,3 +243,98 @@ impl NormalizedMessages {
         &self.messages
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    struct RenderCounter {
+        renders: Rc<Cell<usize>>,
+    }
+
+    impl Element for RenderCounter {
+        fn render(&self) {
+            self.renders.set(self.renders.get() + 1);
+        }
+
+        fn update(&mut self, _message: &Message) -> Option<UiAction> {
+            None
+        }
+
+        fn handle_event(&self, _event: &Event) -> Vec<Message> {
+            vec![]
+        }
+
+        fn z_value(&self) -> i32 {
+            0
+        }
+    }
+
+    fn counter() -> (Box<RenderCounter>, Rc<Cell<usize>>) {
+        let renders = Rc::new(Cell::new(0));
+        (
+            Box::new(RenderCounter {
+                renders: renders.clone(),
+            }),
+            renders,
+        )
+    }
+
+    #[test]
+    fn test_hidden_layer_is_not_rendered() {
+        let mut presenter = Presenter::new();
+        let (hints, hint_renders) = counter();
+        let (board, board_renders) = counter();
+        presenter.add_element_to_layer(hints, "hints");
+        presenter.add_element(board);
+
+        presenter.render();
+        assert_eq!(hint_renders.get(), 1);
+        assert_eq!(board_renders.get(), 1);
+
+        presenter.set_layer_visible("hints", false);
+        presenter.render();
+        assert_eq!(hint_renders.get(), 1);
+        assert_eq!(board_renders.get(), 2);
+
+        presenter.set_layer_visible("hints", true);
+        presenter.render();
+        assert_eq!(hint_renders.get(), 2);
+    }
+
+    #[test]
+    fn test_element_count() {
+        let mut presenter = Presenter::new();
+        assert_eq!(presenter.element_count(), 0);
+        for _ in 0..3 {
+            presenter.add_element(counter().0);
+        }
+        presenter.add_element_to_layer(counter().0, "hints");
+        assert_eq!(presenter.element_count(), 4);
+        assert_eq!(presenter.subscriber_count(), 4);
+
+        presenter.clear_all();
+        assert_eq!(presenter.element_count(), 0);
+        assert_eq!(presenter.subscriber_count(), 0);
+
+        presenter.add_element(counter().0);
+        presenter.add_element_inactive(counter().0);
+        assert_eq!(presenter.element_count(), 2);
+        assert_eq!(presenter.subscriber_count(), 1);
+    }
+
+    #[test]
+    fn test_layer_visibility_survives_clear() {
+        let mut presenter = Presenter::new();
+        presenter.set_layer_visible("hints", false);
+        presenter.clear_all();
+
+        let (hints, hint_renders) = counter();
+        presenter.add_element_to_layer(hints, "hints");
+        presenter.render();
+        assert!(!presenter.is_layer_visible("hints"));
+        assert_eq!(hint_renders.get(), 0);
+    }
+}
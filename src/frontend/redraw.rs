@@ -0,0 +1,63 @@
+use super::frontend::UiStatus;
+
+// frames still drawn after the last change, every buffer of the swap chain has to hold the
+// final picture before drawing can stop
+const SETTLE_FRAMES: u32 = 2;
+
+// decides whether a frame has to be drawn when only redrawing on changes
+pub struct RedrawTracker {
+    enabled: bool,
+    clean_frames: u32,
+}
+
+impl RedrawTracker {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            clean_frames: 0,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clean_frames = 0;
+    }
+
+    // changed covers input and rebuilds, a running animation always draws
+    pub fn frame(&mut self, changed: bool, status: UiStatus) -> bool {
+        if changed || status == UiStatus::Busy {
+            self.clean_frames = 0;
+        }
+        if !self.enabled {
+            return true;
+        }
+        if self.clean_frames >= SETTLE_FRAMES {
+            return false;
+        }
+        self.clean_frames += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_idle_frames_skip_until_input() {
+        let mut tracker = RedrawTracker::new(true);
+        assert!(tracker.frame(true, UiStatus::Idle));
+        for _ in 1..SETTLE_FRAMES {
+            assert!(tracker.frame(false, UiStatus::Idle));
+        }
+        assert!(!tracker.frame(false, UiStatus::Idle));
+        assert!(!tracker.frame(false, UiStatus::Idle));
+
+        // input and animations wake the loop
+        assert!(tracker.frame(true, UiStatus::Idle));
+        assert!(tracker.frame(false, UiStatus::Busy));
+
+        let mut always = RedrawTracker::new(false);
+        assert!((0..5).all(|_| always.frame(false, UiStatus::Idle)));
+    }
+}
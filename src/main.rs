@@ -6,10 +6,12 @@ use crate::core::entities::Piece;
 use crate::core::coord::HexCoord;
 
 use crate::core::board::Board;
-use crate::core::entities::Player;
 use crate::core::game::Game;
+use crate::core::recorder::MoveRecorder;
 
+use frontend::config::{AI_PONDERING, RECORD_GAME_FILE};
 use frontend::frontend::Frontend;
+use frontend::menu::NewGameMenu;
 use macroquad::prelude::*;
 use macroquad::window::Conf;
 
@@ -31,8 +33,20 @@ async fn main() {
         .await
         .unwrap();
 
-    let frontend = Frontend::new(&board, font, 1024, 1024, 2., 2.);
-    let mut game = Game::new(Player::White, Box::new(frontend), board, 5);
+    let mut frontend = Frontend::new(&board, font, 1024, 1024, 2., 2.);
+    let mut menu = NewGameMenu::new();
+    let config = loop {
+        if let Some(config) = frontend.tick_menu(&mut menu) {
+            break config;
+        }
+        next_frame().await
+    };
+
+    let mut game =
+        Game::from_config(Box::new(frontend), board, config).with_pondering(AI_PONDERING);
+    if let Some(path) = RECORD_GAME_FILE {
+        game = game.with_recorder(MoveRecorder::new(path).unwrap());
+    }
 
     loop {
         game.tick();